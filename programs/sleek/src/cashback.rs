@@ -0,0 +1,142 @@
+//! BONK cashback issuance shared by every source of cashback
+//!
+//! Mints go through `mint_cashback`, which enforces the per-user cap and the
+//! program-wide budget before minting and keeps `total_cashback_minted` in step.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, MintTo};
+
+use crate::math::checked_add;
+use crate::{CashbackMinted, CashbackReason, SleekError, SleekState};
+
+/// Limit `cashback` to what the user may still earn under the per-user cap
+pub fn apply_cashback_cap(cashback: u64, earned: u64, sleek_state: &SleekState) -> Result<u64> {
+    if sleek_state.max_cashback_per_user == 0 {
+        return Ok(cashback);
+    }
+
+    let remaining = sleek_state
+        .max_cashback_per_user
+        .saturating_sub(earned);
+    if cashback <= remaining {
+        return Ok(cashback);
+    }
+
+    require!(
+        !sleek_state.revert_on_cashback_cap,
+        SleekError::CashbackCapReached
+    );
+    Ok(remaining)
+}
+
+/// Limit `cashback` to what remains of the program-wide budget once `minted`
+/// has already been issued
+pub fn apply_cashback_budget(cashback: u64, minted: u64, sleek_state: &SleekState) -> Result<u64> {
+    if sleek_state.max_total_cashback == 0 {
+        return Ok(cashback);
+    }
+
+    let remaining = sleek_state.max_total_cashback.saturating_sub(minted);
+    if cashback <= remaining {
+        return Ok(cashback);
+    }
+
+    require!(
+        !sleek_state.revert_on_cashback_budget,
+        SleekError::CashbackBudgetExhausted
+    );
+    Ok(remaining)
+}
+
+/// `cashback` limited by the per-user cap when `earned` holds the user's
+/// lifetime total, then by what is left of the program-wide budget
+pub fn limit_cashback(cashback: u64, earned: Option<u64>, sleek_state: &SleekState) -> Result<u64> {
+    let cashback = match earned {
+        Some(earned) => apply_cashback_cap(cashback, earned, sleek_state)?,
+        None => cashback,
+    };
+    apply_cashback_budget(cashback, sleek_state.total_cashback_minted, sleek_state)
+}
+
+/// Mint up to `amount` BONK to `user` within the caps, count it toward
+/// `total_cashback_minted` and emit `CashbackMinted`; returns the amount minted
+pub fn mint_cashback<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, MintTo<'info>>,
+    sleek_state: &mut SleekState,
+    earned: Option<u64>,
+    user: Pubkey,
+    amount: u64,
+    reason: CashbackReason,
+) -> Result<u64> {
+    let amount = limit_cashback(amount, earned, sleek_state)?;
+    token::mint_to(ctx, amount)?;
+    sleek_state.total_cashback_minted = checked_add(sleek_state.total_cashback_minted, amount)?;
+
+    emit!(CashbackMinted {
+        user,
+        amount,
+        reason,
+    });
+
+    Ok(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::decode_state;
+    use anchor_lang::Discriminator;
+
+    fn state_with(max_cashback_per_user: u64, max_total_cashback: u64, minted: u64) -> SleekState {
+        let mut state = decode_state(&SleekState::DISCRIMINATOR, None).unwrap();
+        state.max_cashback_per_user = max_cashback_per_user;
+        state.max_total_cashback = max_total_cashback;
+        state.total_cashback_minted = minted;
+        state
+    }
+
+    #[test]
+    fn zero_limits_leave_cashback_unbounded() {
+        let state = state_with(0, 0, u64::MAX - 1);
+        assert_eq!(limit_cashback(1_000, Some(u64::MAX), &state).unwrap(), 1_000);
+        assert_eq!(limit_cashback(1_000, None, &state).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn trims_to_the_tighter_limit() {
+        // 300 left for the user, 500 left in the budget
+        let state = state_with(1_000, 10_000, 9_500);
+        assert_eq!(limit_cashback(200, Some(700), &state).unwrap(), 200);
+        assert_eq!(limit_cashback(400, Some(700), &state).unwrap(), 300);
+        assert_eq!(limit_cashback(800, None, &state).unwrap(), 500);
+
+        // 900 left for the user, 100 left in the budget
+        let state = state_with(1_000, 10_000, 9_900);
+        assert_eq!(limit_cashback(400, Some(100), &state).unwrap(), 100);
+    }
+
+    #[test]
+    fn exhausted_limits_trim_to_zero() {
+        let state = state_with(1_000, 10_000, 10_000);
+        assert_eq!(limit_cashback(400, Some(0), &state).unwrap(), 0);
+        assert_eq!(limit_cashback(400, Some(2_000), &state).unwrap(), 0);
+    }
+
+    #[test]
+    fn reverts_when_configured() {
+        let mut state = state_with(1_000, 10_000, 9_500);
+        state.revert_on_cashback_cap = true;
+        assert_eq!(
+            limit_cashback(400, Some(700), &state).unwrap_err(),
+            error!(SleekError::CashbackCapReached)
+        );
+        assert_eq!(limit_cashback(400, None, &state).unwrap(), 400);
+
+        state.revert_on_cashback_budget = true;
+        assert_eq!(
+            limit_cashback(800, None, &state).unwrap_err(),
+            error!(SleekError::CashbackBudgetExhausted)
+        );
+        assert_eq!(limit_cashback(500, None, &state).unwrap(), 500);
+    }
+}
@@ -0,0 +1,123 @@
+//! The timestamp expiry, renewal, grace and cooldown checks run against
+//!
+//! Builds with the `test-clock` feature read it from a `TestClock` account
+//! passed as the first remaining account, so tests can move time without the
+//! validator's clock warp. No instruction creates that account; tests preload
+//! the fixtures in `tests/fixtures/clock` into the local validator. Every
+//! other build only reads the sysvar clock.
+
+use anchor_lang::prelude::*;
+
+/// Timestamp injected in place of the sysvar clock
+#[cfg(feature = "test-clock")]
+#[account]
+pub struct TestClock {
+    pub unix_timestamp: i64,
+}
+
+/// The current unix timestamp
+pub fn now(remaining_accounts: &[AccountInfo]) -> Result<i64> {
+    #[cfg(feature = "test-clock")]
+    if let Some(clock) = remaining_accounts
+        .first()
+        .filter(|info| info.owner == &crate::ID)
+        .and_then(|info| TestClock::try_deserialize(&mut &info.data.borrow()[..]).ok())
+    {
+        return Ok(clock.unix_timestamp);
+    }
+    #[cfg(not(feature = "test-clock"))]
+    let _ = remaining_accounts;
+
+    Ok(Clock::get()?.unix_timestamp)
+}
+
+#[cfg(all(test, feature = "test-clock"))]
+mod tests {
+    use super::*;
+    use crate::{
+        mark_expired, SleekError, SleekState, Subscription, SubscriptionStatus, UserProfile,
+    };
+    use anchor_lang::Discriminator;
+
+    /// Serialized `TestClock` data reading `unix_timestamp`
+    fn clock_data(unix_timestamp: i64) -> Vec<u8> {
+        let mut data = Vec::new();
+        TestClock { unix_timestamp }
+            .try_serialize(&mut data)
+            .unwrap();
+        data
+    }
+
+    /// `now` with a `TestClock` reading `unix_timestamp` owned by `owner`
+    fn now_from(unix_timestamp: i64, owner: &Pubkey) -> Result<i64> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = clock_data(unix_timestamp);
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            owner,
+            false,
+            0,
+        );
+        now(&[info])
+    }
+
+    /// An account of type `T` with every field zeroed
+    fn zeroed<T: AccountDeserialize + Discriminator>(space: usize) -> T {
+        let mut data = T::DISCRIMINATOR.to_vec();
+        data.resize(8 + space, 0);
+        T::try_deserialize(&mut &data[..]).unwrap()
+    }
+
+    #[test]
+    fn reads_an_injected_clock() {
+        assert_eq!(now_from(1_700_000_000, &crate::ID).unwrap(), 1_700_000_000);
+    }
+
+    #[test]
+    fn ignores_a_clock_owned_by_another_program() {
+        // Falls through to the sysvar, which is unavailable off-chain
+        assert!(now_from(1_700_000_000, &Pubkey::new_unique()).is_err());
+    }
+
+    #[test]
+    fn expires_only_once_the_injected_clock_passes_the_grace_window() {
+        let mut subscription: Subscription = zeroed(Subscription::INIT_SPACE);
+        subscription.status = SubscriptionStatus::Active;
+        subscription.activation_date = 1_000;
+        subscription.expiration_date = 2_000;
+        let mut user_profile: UserProfile = zeroed(UserProfile::INIT_SPACE);
+        user_profile.active_subscriptions = 1;
+        let mut sleek_state: SleekState = zeroed(SleekState::INIT_SPACE);
+        sleek_state.grace_period_secs = 100;
+        sleek_state.total_active_subscriptions = 1;
+
+        assert_eq!(
+            mark_expired(
+                &mut subscription,
+                &mut user_profile,
+                &mut sleek_state,
+                None,
+                now_from(2_100, &crate::ID).unwrap()
+            )
+            .unwrap_err(),
+            error!(SleekError::NotYetExpired)
+        );
+
+        mark_expired(
+            &mut subscription,
+            &mut user_profile,
+            &mut sleek_state,
+            None,
+            now_from(2_101, &crate::ID).unwrap(),
+        )
+        .unwrap();
+        assert!(matches!(subscription.status, SubscriptionStatus::Expired));
+        assert_eq!(user_profile.active_subscriptions, 0);
+        assert_eq!(sleek_state.total_active_subscriptions, 0);
+    }
+}
@@ -0,0 +1,119 @@
+//! Admin configuration updates that leave an event trail
+//!
+//! Every admin setter goes through `set_config`/`set_config_key` so each
+//! change emits `ConfigChanged` or `ConfigKeyChanged` with the old and new
+//! value, and the full config history can be replayed from the logs. Plans,
+//! coupons and user profiles go through `set_account_config` and log
+//! `AccountConfigChanged` with the account they belong to.
+
+use anchor_lang::prelude::*;
+
+use crate::{AccountConfigChanged, ConfigChanged, ConfigField, ConfigKeyChanged};
+
+/// Scalar config values, logged as `u64`
+pub trait ConfigValue: Copy {
+    /// The value as logged; signed values keep their two's-complement bits
+    fn bits(self) -> u64;
+}
+
+macro_rules! impl_config_value {
+    ($($t:ty),*) => {
+        $(impl ConfigValue for $t {
+            fn bits(self) -> u64 {
+                self as u64
+            }
+        })*
+    };
+}
+
+impl_config_value!(u8, u16, u32, u64, i64, bool);
+
+/// Set `slot` to `new_value`, logging the change by `admin`; `index` is the
+/// tier or loyalty level of per-level fields and zero otherwise
+pub fn set_config<T: ConfigValue>(
+    admin: Pubkey,
+    field: ConfigField,
+    index: u8,
+    slot: &mut T,
+    new_value: T,
+) {
+    emit!(ConfigChanged {
+        field,
+        index,
+        old_value: slot.bits(),
+        new_value: new_value.bits(),
+        admin,
+    });
+    *slot = new_value;
+}
+
+/// Set `slot` to `new_value`, logging the change by `admin`
+pub fn set_config_key(admin: Pubkey, field: ConfigField, slot: &mut Pubkey, new_value: Pubkey) {
+    emit!(ConfigKeyChanged {
+        field,
+        old_value: *slot,
+        new_value,
+        admin,
+    });
+    *slot = new_value;
+}
+
+/// Set `slot` of the plan, coupon or user profile at `account` to `new_value`,
+/// logging the change by `admin`
+pub fn set_account_config<T: ConfigValue>(
+    admin: Pubkey,
+    field: ConfigField,
+    account: Pubkey,
+    slot: &mut T,
+    new_value: T,
+) {
+    emit!(AccountConfigChanged {
+        field,
+        account,
+        old_value: slot.bits(),
+        new_value: new_value.bits(),
+        admin,
+    });
+    *slot = new_value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logs_values_as_u64_bits() {
+        assert_eq!(true.bits(), 1);
+        assert_eq!(false.bits(), 0);
+        assert_eq!(u16::MAX.bits(), 65_535);
+        assert_eq!(2_592_000i64.bits(), 2_592_000);
+        assert_eq!((-1i64).bits(), u64::MAX);
+    }
+
+    #[test]
+    fn sets_the_new_value() {
+        let mut cashback_bps = 100u16;
+        set_config(Pubkey::default(), ConfigField::CashbackBps, 0, &mut cashback_bps, 250);
+        assert_eq!(cashback_bps, 250);
+
+        let mut fee_recipient = Pubkey::default();
+        let new_recipient = Pubkey::new_unique();
+        set_config_key(
+            Pubkey::default(),
+            ConfigField::FeeRecipient,
+            &mut fee_recipient,
+            new_recipient,
+        );
+        assert_eq!(fee_recipient, new_recipient);
+
+        let mut plan_active = true;
+        set_account_config(
+            Pubkey::default(),
+            ConfigField::PlanActive,
+            Pubkey::new_unique(),
+            &mut plan_active,
+            false,
+        );
+        assert!(!plan_active);
+    }
+}
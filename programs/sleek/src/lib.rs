@@ -1,8 +1,116 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::metadata::{
+    self, mpl_token_metadata::types::DataV2, CreateMetadataAccountsV3, Metadata,
+};
+use anchor_spl::token::{
+    self, spl_token::instruction::AuthorityType, spl_token::native_mint, Approve, Mint, Token,
+    TokenAccount, Transfer,
+};
+
+mod cashback;
+mod clock;
+mod config;
+mod math;
+mod migration;
+mod oracle;
+mod payment;
+mod status;
+
+use math::{
+    add_secs, checked_add, checked_mul, checked_mul_div, checked_pow10, checked_sub, elapsed_secs,
+};
+use cashback::{limit_cashback, mint_cashback};
+use config::{set_account_config, set_config, set_config_key};
+use migration::{decode_state, upgrade_state, STATE_VERSION};
+use payment::{PaymentAccounts, PaymentBumps, PaymentRequest};
+use status::transition;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
+/// Length of a single billing cycle (30 days)
+pub const SUBSCRIPTION_DURATION: i64 = 30 * 24 * 60 * 60;
+
+/// Subscription ids held by each `SubscriptionIndex` page
+pub const SUBSCRIPTION_INDEX_PAGE_SIZE: usize = 50;
+
+/// Maximum length of a plan name
+pub const MAX_PLAN_NAME_LEN: usize = 32;
+
+/// Maximum length of a subscription's metadata URI
+pub const MAX_METADATA_URI_LEN: usize = 200;
+
+/// Maximum length of a subscription's accounting tag
+pub const MAX_TAG_LEN: usize = 32;
+
+/// Seconds in a day
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Furthest ahead of now a subscription may be paid up (365 days)
+pub const MAX_SUBSCRIPTION_HORIZON: i64 = 365 * 24 * 60 * 60;
+
+/// Length of an annual billing period (365 days)
+pub const ANNUAL_DURATION: i64 = 365 * 24 * 60 * 60;
+
+/// Monthly cycles an annual payment is priced at before its discount
+pub const MONTHS_PER_YEAR: u64 = 12;
+
+/// Default time after activation a cancellation is still refunded (7 days)
+pub const DEFAULT_REFUND_WINDOW: i64 = 7 * 24 * 60 * 60;
+
+/// Default time savings stay locked after the latest deposit (30 days)
+pub const DEFAULT_SAVINGS_LOCK: i64 = 30 * 24 * 60 * 60;
+
+/// Default consecutive failed recurring charges before a subscription expires
+pub const DEFAULT_MAX_RENEWAL_FAILURES: u8 = 3;
+
+/// Default wait before a failed recurring charge may be retried (1 day)
+pub const DEFAULT_RENEWAL_RETRY_SECS: i64 = 24 * 60 * 60;
+
+/// First globally unique subscription id handed out; ids below it are left to
+/// clients that pick their own
+pub const AUTO_SUBSCRIPTION_ID_START: u64 = 1 << 63;
+
+/// Default shortest custom duration a payment may buy (1 day)
+pub const DEFAULT_MIN_DURATION: i64 = 24 * 60 * 60;
+
+/// Basis point denominator (10000 = 100%)
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Cashback rate applied on initialize (10%)
+pub const DEFAULT_CASHBACK_BPS: u16 = 1_000;
+
+/// Highest cashback rate the authority may configure (50%)
+pub const MAX_CASHBACK_BPS: u16 = 5_000;
+
+/// Number of subscription tiers
+pub const TIER_COUNT: usize = 3;
+
+/// Number of loyalty tiers (Bronze, Silver, Gold)
+pub const LOYALTY_TIER_COUNT: usize = 3;
+
+/// Payments needed to reach each loyalty tier on initialize
+pub const DEFAULT_LOYALTY_THRESHOLDS: [u64; LOYALTY_TIER_COUNT] = [0, 5, 20];
+
+/// Cashback multiplier of each loyalty tier on initialize (1x, 1.25x, 1.5x)
+pub const DEFAULT_LOYALTY_MULTIPLIERS_BPS: [u16; LOYALTY_TIER_COUNT] = [10_000, 12_500, 15_000];
+
+/// Default minimum age before a payment record can be closed (90 days)
+pub const DEFAULT_PAYMENT_RETENTION: i64 = 90 * 24 * 60 * 60;
+
+/// Maximum number of whitelisted payment mints
+pub const MAX_PAYMENT_MINTS: usize = 8;
+
+/// Maximum number of secondary admins
+pub const MAX_ADMINS: usize = 5;
+
+/// Token metadata symbol for subscription NFTs
+pub const SUBSCRIPTION_NFT_SYMBOL: &str = "SLEEK";
+
+/// Metadata service base URI; the subscription id is appended
+pub const SUBSCRIPTION_NFT_URI_BASE: &str = "https://sleek.app/metadata/subscription/";
+
 #[program]
 pub mod sleek {
     use super::*;
@@ -11,240 +119,4836 @@ pub mod sleek {
     pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
         let sleek_state = &mut ctx.accounts.sleek_state;
         sleek_state.authority = ctx.accounts.authority.key();
-        sleek_state.bump = *ctx.bumps.get("sleek_state").unwrap();
+        sleek_state.bonk_mint = ctx.accounts.bonk_mint.key();
+        sleek_state.bump = ctx.bumps.sleek_state;
         sleek_state.total_subscriptions = 0;
         sleek_state.total_payments = 0;
         sleek_state.total_cashback_minted = 0;
+        sleek_state.cashback_bps = DEFAULT_CASHBACK_BPS;
+        sleek_state.paused = false;
+        sleek_state.pending_authority = None;
+        sleek_state.redemption_count = 0;
+        sleek_state.tier_configs = [TierConfig {
+            duration_secs: SUBSCRIPTION_DURATION,
+            bonus_cashback_bps: 0,
+        }; TIER_COUNT];
+        sleek_state.payment_retention_secs = DEFAULT_PAYMENT_RETENTION;
+        sleek_state.allowed_payment_mints = Vec::new();
+        sleek_state.max_cashback_per_user = 0;
+        sleek_state.revert_on_cashback_cap = false;
+        sleek_state.referral_bonus_bps = 0;
+        sleek_state.grace_period_secs = 0;
+        sleek_state.loyalty_thresholds = DEFAULT_LOYALTY_THRESHOLDS;
+        sleek_state.loyalty_multipliers_bps = DEFAULT_LOYALTY_MULTIPLIERS_BPS;
+        sleek_state.min_duration_secs = DEFAULT_MIN_DURATION;
+        sleek_state.max_duration_secs = MAX_SUBSCRIPTION_HORIZON;
+        sleek_state.min_cashback = 0;
+        sleek_state.airdrop_count = 0;
+        sleek_state.total_airdropped = 0;
+        sleek_state.max_airdrop_total = 0;
+        sleek_state.fee_recipient = Pubkey::default();
+        sleek_state.fee_bps = 0;
+        sleek_state.redemption_cooldown_secs = 0;
+        sleek_state.max_subscriptions_per_user = 0;
+        sleek_state.promo_start = 0;
+        sleek_state.promo_end = 0;
+        sleek_state.promo_cashback_bps = 0;
+        sleek_state.extend_from_now_if_expired = true;
+        sleek_state.redemptions_paused = false;
+        sleek_state.version = STATE_VERSION;
+        sleek_state.payment_decimals = 0;
+        sleek_state.cashback_decimals = 0;
+        sleek_state.min_redemption_amount = 0;
+        sleek_state.allowlist_enabled = false;
+        sleek_state.total_active_subscriptions = 0;
+        sleek_state.keeper_reward = 0;
+        sleek_state.keeper_reward_budget = 0;
+        sleek_state.annual_discount_bps = 0;
+        sleek_state.refund_window_secs = DEFAULT_REFUND_WINDOW;
+        sleek_state.trial_secs = 0;
+        sleek_state.bonk_per_lamport = 0;
+        sleek_state.max_total_cashback = 0;
+        sleek_state.revert_on_cashback_budget = false;
+        sleek_state.admins = Vec::new();
+        sleek_state.savings_lock_secs = DEFAULT_SAVINGS_LOCK;
+        sleek_state.max_renewal_failures = DEFAULT_MAX_RENEWAL_FAILURES;
+        sleek_state.next_subscription_id = AUTO_SUBSCRIPTION_ID_START;
+        sleek_state.renewal_retry_secs = DEFAULT_RENEWAL_RETRY_SECS;
         Ok(())
     }
 
-    /// Process subscription payment
-    pub fn process_subscription_payment(
-        ctx: Context<ProcessPayment>,
-        subscription_id: u64,
-        amount: u64,
-        sol_amount: u64,
-    ) -> Result<()> {
-        let payment = &mut ctx.accounts.payment;
+    /// Update the cashback rate (admin only)
+    pub fn set_cashback_rate(ctx: Context<UpdateConfig>, cashback_bps: u16) -> Result<()> {
+        require!(
+            cashback_bps <= MAX_CASHBACK_BPS,
+            SleekError::InvalidCashbackRate
+        );
+
+        let admin = ctx.accounts.authority.key();
         let sleek_state = &mut ctx.accounts.sleek_state;
-        
-        // Set payment details
-        payment.user = ctx.accounts.user.key();
-        payment.subscription_id = subscription_id;
-        payment.amount = amount;
-        payment.sol_amount = sol_amount;
-        payment.status = PaymentStatus::Completed;
-        payment.timestamp = Clock::get()?.unix_timestamp;
-        payment.bump = *ctx.bumps.get("payment").unwrap();
-
-        // Transfer SOL from user to authority
-        let transfer_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.user_token_account.to_account_info(),
-                to: ctx.accounts.authority_token_account.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
-            },
+        let old_cashback_bps = sleek_state.cashback_bps;
+        set_config(admin, ConfigField::CashbackBps, 0, &mut sleek_state.cashback_bps, cashback_bps);
+
+        emit!(CashbackRateUpdated {
+            old_cashback_bps,
+            new_cashback_bps: cashback_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Update a tier's billing duration and bonus cashback (admin only)
+    pub fn set_tier_config(
+        ctx: Context<UpdateConfig>,
+        tier: Tier,
+        duration_secs: i64,
+        bonus_cashback_bps: u16,
+    ) -> Result<()> {
+        require!(duration_secs > 0, SleekError::InvalidTierConfig);
+        require!(
+            bonus_cashback_bps <= MAX_CASHBACK_BPS,
+            SleekError::InvalidTierConfig
         );
-        token::transfer(transfer_ctx, sol_amount)?;
 
-        // Calculate and mint cashback (10% of payment)
-        let cashback_amount = amount * 10 / 100; // 10% cashback
-        
-        // Mint BONK tokens to user
-        let mint_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            token::MintTo {
-                mint: ctx.accounts.bonk_mint.to_account_info(),
-                to: ctx.accounts.user_bonk_account.to_account_info(),
-                authority: ctx.accounts.authority.to_account_info(),
-            },
+        let admin = ctx.accounts.authority.key();
+        let index = tier.index();
+        let tier_config = &mut ctx.accounts.sleek_state.tier_configs[index];
+        set_config(
+            admin,
+            ConfigField::TierDurationSecs,
+            index as u8,
+            &mut tier_config.duration_secs,
+            duration_secs,
+        );
+        set_config(
+            admin,
+            ConfigField::TierBonusCashbackBps,
+            index as u8,
+            &mut tier_config.bonus_cashback_bps,
+            bonus_cashback_bps,
         );
-        token::mint_to(mint_ctx, cashback_amount)?;
 
-        // Update global stats
-        sleek_state.total_payments += 1;
-        sleek_state.total_cashback_minted += cashback_amount;
+        Ok(())
+    }
 
-        // Create subscription NFT
-        let subscription = &mut ctx.accounts.subscription;
-        subscription.user = ctx.accounts.user.key();
-        subscription.subscription_id = subscription_id;
-        subscription.amount = amount;
-        subscription.status = SubscriptionStatus::Active;
-        subscription.activation_date = Clock::get()?.unix_timestamp;
-        subscription.expiration_date = Clock::get()?.unix_timestamp + (30 * 24 * 60 * 60); // 30 days
-        subscription.bump = *ctx.bumps.get("subscription").unwrap();
+    /// Update the feature bits a tier grants subscriptions bought without a plan (admin only)
+    pub fn set_tier_features(ctx: Context<UpdateConfig>, tier: Tier, features: u32) -> Result<()> {
+        let index = tier.index();
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::TierFeatures,
+            index as u8,
+            &mut ctx.accounts.sleek_state.tier_features[index],
+            features,
+        );
+        Ok(())
+    }
 
-        sleek_state.total_subscriptions += 1;
+    /// Update how long payment records must be kept before closing (admin only)
+    pub fn set_payment_retention(ctx: Context<UpdateConfig>, retention_secs: i64) -> Result<()> {
+        require!(retention_secs >= 0, SleekError::InvalidAmount);
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::PaymentRetentionSecs,
+            0,
+            &mut ctx.accounts.sleek_state.payment_retention_secs,
+            retention_secs,
+        );
+        Ok(())
+    }
 
-        emit!(PaymentProcessed {
-            user: ctx.accounts.user.key(),
-            subscription_id,
-            amount,
-            cashback_amount,
+    /// Whitelist a token mint for subscription payments (authority only)
+    pub fn add_payment_mint(ctx: Context<UpdateTreasuryConfig>, mint: Pubkey) -> Result<()> {
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        require!(mint != sleek_state.bonk_mint, SleekError::InvalidMint);
+
+        if sleek_state.allowed_payment_mints.contains(&mint) {
+            return Ok(());
+        }
+
+        require!(
+            sleek_state.allowed_payment_mints.len() < MAX_PAYMENT_MINTS,
+            SleekError::PaymentMintListFull
+        );
+        sleek_state.allowed_payment_mints.push(mint);
+
+        emit!(ConfigKeyChanged {
+            field: ConfigField::PaymentMint,
+            old_value: Pubkey::default(),
+            new_value: mint,
+            admin: ctx.accounts.authority.key(),
         });
 
         Ok(())
     }
 
-    /// Redeem BONK cashback
-    pub fn redeem_cashback(
-        ctx: Context<RedeemCashback>,
-        amount: u64,
+    /// Remove a token mint from the payment whitelist (authority only)
+    pub fn remove_payment_mint(ctx: Context<UpdateTreasuryConfig>, mint: Pubkey) -> Result<()> {
+        let sleek_state = &mut ctx.accounts.sleek_state;
+
+        let position = sleek_state
+            .allowed_payment_mints
+            .iter()
+            .position(|m| *m == mint)
+            .ok_or(SleekError::UnsupportedPaymentMint)?;
+        sleek_state.allowed_payment_mints.remove(position);
+
+        emit!(ConfigKeyChanged {
+            field: ConfigField::PaymentMint,
+            old_value: mint,
+            new_value: Pubkey::default(),
+            admin: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Configure the per-user lifetime cashback cap (admin only)
+    ///
+    /// A cap of zero disables it. When `revert_on_cap` is false, a payment that
+    /// would cross the cap mints only the remaining allowance instead of failing.
+    pub fn set_cashback_cap(
+        ctx: Context<UpdateConfig>,
+        max_cashback_per_user: u64,
+        revert_on_cap: bool,
     ) -> Result<()> {
-        let redemption = &mut ctx.accounts.redemption;
-        
-        // Set redemption details
-        redemption.user = ctx.accounts.user.key();
-        redemption.amount = amount;
-        redemption.timestamp = Clock::get()?.unix_timestamp;
-        redemption.bump = *ctx.bumps.get("redemption").unwrap();
+        let admin = ctx.accounts.authority.key();
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        set_config(
+            admin,
+            ConfigField::MaxCashbackPerUser,
+            0,
+            &mut sleek_state.max_cashback_per_user,
+            max_cashback_per_user,
+        );
+        set_config(
+            admin,
+            ConfigField::RevertOnCashbackCap,
+            0,
+            &mut sleek_state.revert_on_cashback_cap,
+            revert_on_cap,
+        );
+        Ok(())
+    }
 
-        // Burn BONK tokens from user
-        let burn_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            token::Burn {
-                mint: ctx.accounts.bonk_mint.to_account_info(),
-                from: ctx.accounts.user_bonk_account.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
-            },
+    /// Configure the program-wide cashback budget (authority only)
+    ///
+    /// A budget of zero disables it. When `revert_on_budget` is false, a mint
+    /// that would cross the budget mints only what remains instead of failing.
+    pub fn set_cashback_budget(
+        ctx: Context<UpdateTreasuryConfig>,
+        max_total_cashback: u64,
+        revert_on_budget: bool,
+    ) -> Result<()> {
+        let admin = ctx.accounts.authority.key();
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        set_config(
+            admin,
+            ConfigField::MaxTotalCashback,
+            0,
+            &mut sleek_state.max_total_cashback,
+            max_total_cashback,
         );
-        token::burn(burn_ctx, amount)?;
+        set_config(
+            admin,
+            ConfigField::RevertOnCashbackBudget,
+            0,
+            &mut sleek_state.revert_on_cashback_budget,
+            revert_on_budget,
+        );
+        Ok(())
+    }
 
-        emit!(CashbackRedeemed {
-            user: ctx.accounts.user.key(),
-            amount,
-        });
+    /// Update the minimum cashback earned on any nonzero payment (admin only)
+    pub fn set_min_cashback(ctx: Context<UpdateConfig>, min_cashback: u64) -> Result<()> {
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::MinCashback,
+            0,
+            &mut ctx.accounts.sleek_state.min_cashback,
+            min_cashback,
+        );
+        Ok(())
+    }
 
+    /// Update the smallest payment accepted, in the payment token's base units;
+    /// zero disables the minimum (admin only)
+    pub fn set_min_payment(ctx: Context<UpdateConfig>, min_payment_amount: u64) -> Result<()> {
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::MinPaymentAmount,
+            0,
+            &mut ctx.accounts.sleek_state.min_payment_amount,
+            min_payment_amount,
+        );
         Ok(())
     }
 
-    /// Cancel subscription
-    pub fn cancel_subscription(
-        ctx: Context<CancelSubscription>,
+    /// Update how many consecutive failed recurring charges expire a
+    /// subscription; zero never expires it (admin only)
+    pub fn set_max_renewal_failures(ctx: Context<UpdateConfig>, max_renewal_failures: u8) -> Result<()> {
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::MaxRenewalFailures,
+            0,
+            &mut ctx.accounts.sleek_state.max_renewal_failures,
+            max_renewal_failures,
+        );
+        Ok(())
+    }
+
+    /// Update how long a failed recurring charge waits before it may be
+    /// retried (admin only)
+    pub fn set_renewal_retry_secs(ctx: Context<UpdateConfig>, renewal_retry_secs: i64) -> Result<()> {
+        require!(renewal_retry_secs > 0, SleekError::InvalidDuration);
+
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::RenewalRetrySecs,
+            0,
+            &mut ctx.accounts.sleek_state.renewal_retry_secs,
+            renewal_retry_secs,
+        );
+        Ok(())
+    }
+
+    /// Update the Pyth SOL/USD feed SOL payments are checked against, how far a
+    /// payment may stray from its price and how old that price may be; a zero
+    /// feed id disables the check (authority only)
+    pub fn set_price_feed(
+        ctx: Context<UpdateTreasuryConfig>,
+        price_feed_id: [u8; 32],
+        price_tolerance_bps: u16,
+        max_price_age_secs: i64,
     ) -> Result<()> {
-        let subscription = &mut ctx.accounts.subscription;
-        
         require!(
-            subscription.user == ctx.accounts.user.key(),
-            SleekError::Unauthorized
+            price_tolerance_bps as u64 <= BPS_DENOMINATOR,
+            SleekError::InvalidAmount
         );
-        
+        require!(max_price_age_secs >= 0, SleekError::InvalidDuration);
+        let admin = ctx.accounts.authority.key();
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        emit!(ConfigKeyChanged {
+            field: ConfigField::PriceFeedId,
+            old_value: Pubkey::new_from_array(sleek_state.price_feed_id),
+            new_value: Pubkey::new_from_array(price_feed_id),
+            admin,
+        });
+        sleek_state.price_feed_id = price_feed_id;
+        set_config(
+            admin,
+            ConfigField::PriceToleranceBps,
+            0,
+            &mut sleek_state.price_tolerance_bps,
+            price_tolerance_bps,
+        );
+        set_config(
+            admin,
+            ConfigField::MaxPriceAgeSecs,
+            0,
+            &mut sleek_state.max_price_age_secs,
+            max_price_age_secs,
+        );
+        Ok(())
+    }
+
+    /// Update the one-time cashback bonus for users who resubscribe within
+    /// `winback_window_secs` of cancelling; a zero bonus disables it (admin only)
+    pub fn set_winback(
+        ctx: Context<UpdateConfig>,
+        winback_window_secs: i64,
+        winback_bonus_bps: u16,
+    ) -> Result<()> {
         require!(
-            subscription.status == SubscriptionStatus::Active,
-            SleekError::SubscriptionNotActive
+            winback_bonus_bps <= MAX_CASHBACK_BPS,
+            SleekError::InvalidCashbackRate
+        );
+        require!(winback_window_secs >= 0, SleekError::InvalidDuration);
+        let admin = ctx.accounts.authority.key();
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        set_config(
+            admin,
+            ConfigField::WinbackWindowSecs,
+            0,
+            &mut sleek_state.winback_window_secs,
+            winback_window_secs,
         );
+        set_config(
+            admin,
+            ConfigField::WinbackBonusBps,
+            0,
+            &mut sleek_state.winback_bonus_bps,
+            winback_bonus_bps,
+        );
+        Ok(())
+    }
 
-        subscription.status = SubscriptionStatus::Cancelled;
-        subscription.cancellation_date = Clock::get()?.unix_timestamp;
+    /// Update the cumulative ceiling on airdropped cashback (authority only)
+    pub fn set_airdrop_ceiling(ctx: Context<UpdateTreasuryConfig>, max_airdrop_total: u64) -> Result<()> {
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::MaxAirdropTotal,
+            0,
+            &mut ctx.accounts.sleek_state.max_airdrop_total,
+            max_airdrop_total,
+        );
+        Ok(())
+    }
 
-        emit!(SubscriptionCancelled {
-            user: ctx.accounts.user.key(),
-            subscription_id: subscription.subscription_id,
-        });
+    /// Update the partner share taken from each payment (authority only)
+    pub fn set_fee_split(ctx: Context<UpdateTreasuryConfig>, fee_recipient: Pubkey, fee_bps: u16) -> Result<()> {
+        require!(fee_bps as u64 <= BPS_DENOMINATOR, SleekError::InvalidFeeConfig);
+
+        let admin = ctx.accounts.authority.key();
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        set_config_key(
+            admin,
+            ConfigField::FeeRecipient,
+            &mut sleek_state.fee_recipient,
+            fee_recipient,
+        );
+        set_config(admin, ConfigField::FeeBps, 0, &mut sleek_state.fee_bps, fee_bps);
 
         Ok(())
     }
 
-    /// Get user's cashback balance
-    pub fn get_cashback_balance(ctx: Context<GetCashbackBalance>) -> Result<u64> {
-        let user_bonk_account = &ctx.accounts.user_bonk_account;
-        Ok(user_bonk_account.amount)
+    /// Update the minimum time between a user's redemptions; zero disables it (admin only)
+    pub fn set_redemption_cooldown(
+        ctx: Context<UpdateConfig>,
+        redemption_cooldown_secs: i64,
+    ) -> Result<()> {
+        require!(redemption_cooldown_secs >= 0, SleekError::InvalidAmount);
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::RedemptionCooldownSecs,
+            0,
+            &mut ctx.accounts.sleek_state.redemption_cooldown_secs,
+            redemption_cooldown_secs,
+        );
+        Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + SleekState::INIT_SPACE,
-        seeds = [b"sleek_state"],
-        bump
-    )]
-    pub sleek_state: Account<'info, SleekState>,
-    
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    pub system_program: Program<'info, System>,
-}
+    /// Update the smallest amount a single redemption may burn; zero disables it (admin only)
+    pub fn set_min_redemption_amount(
+        ctx: Context<UpdateConfig>,
+        min_redemption_amount: u64,
+    ) -> Result<()> {
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::MinRedemptionAmount,
+            0,
+            &mut ctx.accounts.sleek_state.min_redemption_amount,
+            min_redemption_amount,
+        );
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct ProcessPayment<'info> {
-    #[account(
-        init,
-        payer = user,
-        space = 8 + Payment::INIT_SPACE,
-        seeds = [b"payment", user.key().as_ref(), &sleek_state.total_payments.to_le_bytes()],
-        bump
-    )]
-    pub payment: Account<'info, Payment>,
-    
-    #[account(
-        init,
-        payer = user,
-        space = 8 + Subscription::INIT_SPACE,
-        seeds = [b"subscription", user.key().as_ref(), &subscription_id.to_le_bytes()],
-        bump
-    )]
-    pub subscription: Account<'info, Subscription>,
-    
-    #[account(
-        mut,
-        seeds = [b"sleek_state"],
-        bump = sleek_state.bump
-    )]
-    pub sleek_state: Account<'info, SleekState>,
-    
-    #[account(mut)]
-    pub user: Signer<'info>,
-    
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub authority_token_account: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub user_bonk_account: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub bonk_mint: Account<'info, Mint>,
-    
-    pub authority: Signer<'info>,
-    
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
+    /// Update the BONK paid to keepers per expired subscription; zero disables it (admin only)
+    pub fn set_keeper_reward(ctx: Context<UpdateConfig>, keeper_reward: u64) -> Result<()> {
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::KeeperReward,
+            0,
+            &mut ctx.accounts.sleek_state.keeper_reward,
+            keeper_reward,
+        );
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct RedeemCashback<'info> {
-    #[account(
-        init,
+    /// Update how long after activation cancellations are refunded; zero disables it (admin only)
+    pub fn set_refund_window(ctx: Context<UpdateConfig>, refund_window_secs: i64) -> Result<()> {
+        require!(refund_window_secs >= 0, SleekError::InvalidAmount);
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::RefundWindowSecs,
+            0,
+            &mut ctx.accounts.sleek_state.refund_window_secs,
+            refund_window_secs,
+        );
+        Ok(())
+    }
+
+    /// Update how long savings stay locked after each deposit (admin only)
+    pub fn set_savings_lock(ctx: Context<UpdateConfig>, savings_lock_secs: i64) -> Result<()> {
+        require!(savings_lock_secs >= 0, SleekError::InvalidAmount);
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::SavingsLockSecs,
+            0,
+            &mut ctx.accounts.sleek_state.savings_lock_secs,
+            savings_lock_secs,
+        );
+        Ok(())
+    }
+
+    /// Update how many payments a user may make per window; zero disables the
+    /// limit (admin only)
+    pub fn set_payment_rate_limit(
+        ctx: Context<UpdateConfig>,
+        max_payments_per_window: u32,
+        payment_window_secs: i64,
+    ) -> Result<()> {
+        require!(payment_window_secs >= 0, SleekError::InvalidDuration);
+        let admin = ctx.accounts.authority.key();
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        set_config(
+            admin,
+            ConfigField::MaxPaymentsPerWindow,
+            0,
+            &mut sleek_state.max_payments_per_window,
+            max_payments_per_window,
+        );
+        set_config(
+            admin,
+            ConfigField::PaymentWindowSecs,
+            0,
+            &mut sleek_state.payment_window_secs,
+            payment_window_secs,
+        );
+        Ok(())
+    }
+
+    /// Update how long a new user's free trial lasts; zero disables trials (admin only)
+    pub fn set_trial_secs(ctx: Context<UpdateConfig>, trial_secs: i64) -> Result<()> {
+        require!(trial_secs >= 0, SleekError::InvalidDuration);
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::TrialSecs,
+            0,
+            &mut ctx.accounts.sleek_state.trial_secs,
+            trial_secs,
+        );
+        Ok(())
+    }
+
+    /// Update the BONK burned per unit of payment discount; zero disables it (authority only)
+    pub fn set_bonk_per_lamport(ctx: Context<UpdateTreasuryConfig>, bonk_per_lamport: u64) -> Result<()> {
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::BonkPerLamport,
+            0,
+            &mut ctx.accounts.sleek_state.bonk_per_lamport,
+            bonk_per_lamport,
+        );
+        Ok(())
+    }
+
+    /// Update how many active subscriptions a user may hold; zero means unlimited (admin only)
+    pub fn set_max_subscriptions_per_user(
+        ctx: Context<UpdateConfig>,
+        max_subscriptions_per_user: u32,
+    ) -> Result<()> {
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::MaxSubscriptionsPerUser,
+            0,
+            &mut ctx.accounts.sleek_state.max_subscriptions_per_user,
+            max_subscriptions_per_user,
+        );
+        Ok(())
+    }
+
+    /// Replace the base cashback rate with `promo_cashback_bps` during
+    /// `[promo_start, promo_end)`; an empty window disables the promo (admin only)
+    pub fn set_promo(
+        ctx: Context<UpdateConfig>,
+        promo_start: i64,
+        promo_end: i64,
+        promo_cashback_bps: u16,
+    ) -> Result<()> {
+        require!(promo_start <= promo_end, SleekError::InvalidPromoWindow);
+        require!(
+            promo_cashback_bps <= MAX_CASHBACK_BPS,
+            SleekError::InvalidCashbackRate
+        );
+
+        let admin = ctx.accounts.authority.key();
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        set_config(admin, ConfigField::PromoStart, 0, &mut sleek_state.promo_start, promo_start);
+        set_config(admin, ConfigField::PromoEnd, 0, &mut sleek_state.promo_end, promo_end);
+        set_config(
+            admin,
+            ConfigField::PromoCashbackBps,
+            0,
+            &mut sleek_state.promo_cashback_bps,
+            promo_cashback_bps,
+        );
+        Ok(())
+    }
+
+    /// Choose whether renewing a lapsed subscription starts the new period at
+    /// now (forgiving the lapse) or at its stale expiration date (admin only)
+    pub fn set_extend_from_now_if_expired(
+        ctx: Context<UpdateConfig>,
+        extend_from_now_if_expired: bool,
+    ) -> Result<()> {
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::ExtendFromNowIfExpired,
+            0,
+            &mut ctx.accounts.sleek_state.extend_from_now_if_expired,
+            extend_from_now_if_expired,
+        );
+        Ok(())
+    }
+
+    /// Record the payment token's and cashback mint's decimals so cashback is
+    /// minted in the right units; equal values leave amounts unscaled (authority only)
+    pub fn set_token_decimals(
+        ctx: Context<UpdateTreasuryConfig>,
+        payment_decimals: u8,
+        cashback_decimals: u8,
+    ) -> Result<()> {
+        checked_pow10(payment_decimals.abs_diff(cashback_decimals))
+            .map_err(|_| error!(SleekError::InvalidDecimals))?;
+
+        let admin = ctx.accounts.authority.key();
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        set_config(
+            admin,
+            ConfigField::PaymentDecimals,
+            0,
+            &mut sleek_state.payment_decimals,
+            payment_decimals,
+        );
+        set_config(
+            admin,
+            ConfigField::CashbackDecimals,
+            0,
+            &mut sleek_state.cashback_decimals,
+            cashback_decimals,
+        );
+        Ok(())
+    }
+
+    /// Update the discount annual payments get on twelve monthly cycles (admin only)
+    pub fn set_annual_discount(ctx: Context<UpdateConfig>, annual_discount_bps: u16) -> Result<()> {
+        require!(
+            (annual_discount_bps as u64) < BPS_DENOMINATOR,
+            SleekError::InvalidDiscount
+        );
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::AnnualDiscountBps,
+            0,
+            &mut ctx.accounts.sleek_state.annual_discount_bps,
+            annual_discount_bps,
+        );
+        Ok(())
+    }
+
+    /// Update the bonus paid to referrers on a referred user's first payment (admin only)
+    pub fn set_referral_bonus(ctx: Context<UpdateConfig>, referral_bonus_bps: u16) -> Result<()> {
+        require!(
+            referral_bonus_bps <= MAX_CASHBACK_BPS,
+            SleekError::InvalidCashbackRate
+        );
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::ReferralBonusBps,
+            0,
+            &mut ctx.accounts.sleek_state.referral_bonus_bps,
+            referral_bonus_bps,
+        );
+        Ok(())
+    }
+
+    /// Update how long a lapsed subscription stays usable (admin only)
+    pub fn set_grace_period(ctx: Context<UpdateConfig>, grace_period_secs: i64) -> Result<()> {
+        require!(grace_period_secs >= 0, SleekError::InvalidAmount);
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::GracePeriodSecs,
+            0,
+            &mut ctx.accounts.sleek_state.grace_period_secs,
+            grace_period_secs,
+        );
+        Ok(())
+    }
+
+    /// Update the range of custom durations a payment may buy (admin only)
+    pub fn set_duration_bounds(
+        ctx: Context<UpdateConfig>,
+        min_duration_secs: i64,
+        max_duration_secs: i64,
+    ) -> Result<()> {
+        require!(
+            0 < min_duration_secs
+                && min_duration_secs <= max_duration_secs
+                && max_duration_secs <= MAX_SUBSCRIPTION_HORIZON,
+            SleekError::InvalidDuration
+        );
+
+        let admin = ctx.accounts.authority.key();
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        set_config(
+            admin,
+            ConfigField::MinDurationSecs,
+            0,
+            &mut sleek_state.min_duration_secs,
+            min_duration_secs,
+        );
+        set_config(
+            admin,
+            ConfigField::MaxDurationSecs,
+            0,
+            &mut sleek_state.max_duration_secs,
+            max_duration_secs,
+        );
+
+        Ok(())
+    }
+
+    /// Create a percentage-off coupon identified by the hash of its code (authority only)
+    pub fn create_coupon(
+        ctx: Context<CreateCoupon>,
+        code_hash: [u8; 32],
+        discount_bps: u16,
+        max_redemptions: u32,
+        expiry: i64,
+    ) -> Result<()> {
+        require!(
+            discount_bps > 0 && discount_bps as u64 <= BPS_DENOMINATOR,
+            SleekError::CouponInvalid
+        );
+        require!(max_redemptions > 0, SleekError::CouponInvalid);
+        require!(
+            expiry > clock::now(ctx.remaining_accounts)?,
+            SleekError::CouponInvalid
+        );
+
+        let admin = ctx.accounts.authority.key();
+        let coupon = &mut ctx.accounts.coupon;
+        let account = coupon.key();
+        coupon.code_hash = code_hash;
+        set_account_config(
+            admin,
+            ConfigField::CouponDiscountBps,
+            account,
+            &mut coupon.discount_bps,
+            discount_bps,
+        );
+        set_account_config(
+            admin,
+            ConfigField::CouponMaxRedemptions,
+            account,
+            &mut coupon.max_redemptions,
+            max_redemptions,
+        );
+        coupon.times_redeemed = 0;
+        set_account_config(admin, ConfigField::CouponExpiry, account, &mut coupon.expiry, expiry);
+        coupon.bump = ctx.bumps.coupon;
+
+        Ok(())
+    }
+
+    /// Create a plan with a fixed price in `payment_mint`, duration, cashback rate
+    /// and tier (authority only)
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_plan(
+        ctx: Context<CreatePlan>,
+        plan_id: u64,
+        name: String,
+        price: u64,
+        duration_secs: i64,
+        cashback_bps: u16,
+        tier: Tier,
+        payment_mint: Pubkey,
+    ) -> Result<()> {
+        validate_plan(&name, price, duration_secs, cashback_bps)?;
+        // Native SOL is priced in lamports; any other mint must be accepted for payment
+        require!(
+            payment_mint == native_mint::ID
+                || ctx.accounts.sleek_state.allowed_payment_mints.contains(&payment_mint),
+            SleekError::UnsupportedPaymentMint
+        );
+
+        let plan = &mut ctx.accounts.plan;
+        plan.plan_id = plan_id;
+        plan.tier = tier;
+        plan.payment_mint = payment_mint;
+        plan.name = name;
+        set_plan_terms(ctx.accounts.authority.key(), plan, price, duration_secs, cashback_bps);
+        set_account_config(
+            ctx.accounts.authority.key(),
+            ConfigField::PlanActive,
+            plan.key(),
+            &mut plan.active,
+            true,
+        );
+        plan.bump = ctx.bumps.plan;
+        plan.active_subscribers = 0;
+        plan.lifetime_subscribers = 0;
+        plan.features = 0;
+
+        Ok(())
+    }
+
+    /// Update a plan's name, price, duration and cashback rate (authority only)
+    pub fn update_plan(
+        ctx: Context<UpdatePlan>,
+        name: String,
+        price: u64,
+        duration_secs: i64,
+        cashback_bps: u16,
+    ) -> Result<()> {
+        validate_plan(&name, price, duration_secs, cashback_bps)?;
+
+        let plan = &mut ctx.accounts.plan;
+        plan.name = name;
+        set_plan_terms(ctx.accounts.authority.key(), plan, price, duration_secs, cashback_bps);
+
+        Ok(())
+    }
+
+    /// Update the feature bits a plan's subscriptions are entitled to (authority only)
+    pub fn set_plan_features(ctx: Context<UpdatePlan>, features: u32) -> Result<()> {
+        let plan = &mut ctx.accounts.plan;
+        set_account_config(
+            ctx.accounts.authority.key(),
+            ConfigField::PlanFeatures,
+            plan.key(),
+            &mut plan.features,
+            features,
+        );
+        Ok(())
+    }
+
+    /// Stop selling a plan (authority only)
+    pub fn deactivate_plan(ctx: Context<UpdatePlan>) -> Result<()> {
+        let plan = &mut ctx.accounts.plan;
+        set_account_config(
+            ctx.accounts.authority.key(),
+            ConfigField::PlanActive,
+            plan.key(),
+            &mut plan.active,
+            false,
+        );
+        Ok(())
+    }
+
+    /// Update loyalty tier payment thresholds and cashback multipliers (admin only)
+    ///
+    /// Thresholds are minimum prior payment counts and must start at zero and
+    /// increase; multipliers are in basis points of the base rate (10000 = 1x).
+    pub fn set_loyalty_tiers(
+        ctx: Context<UpdateConfig>,
+        thresholds: [u64; LOYALTY_TIER_COUNT],
+        multipliers_bps: [u16; LOYALTY_TIER_COUNT],
+    ) -> Result<()> {
+        require!(thresholds[0] == 0, SleekError::InvalidLoyaltyConfig);
+        require!(
+            thresholds.windows(2).all(|w| w[0] < w[1]),
+            SleekError::InvalidLoyaltyConfig
+        );
+
+        let admin = ctx.accounts.authority.key();
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        for level in 0..LOYALTY_TIER_COUNT {
+            set_config(
+                admin,
+                ConfigField::LoyaltyThreshold,
+                level as u8,
+                &mut sleek_state.loyalty_thresholds[level],
+                thresholds[level],
+            );
+            set_config(
+                admin,
+                ConfigField::LoyaltyMultiplierBps,
+                level as u8,
+                &mut sleek_state.loyalty_multipliers_bps[level],
+                multipliers_bps[level],
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Freeze or unfreeze a user's redemptions, e.g. during an investigation (authority only)
+    pub fn set_user_frozen(ctx: Context<SetUserFrozen>, frozen: bool) -> Result<()> {
+        let user_profile = &mut ctx.accounts.user_profile;
+        set_account_config(
+            ctx.accounts.authority.key(),
+            ConfigField::UserFrozen,
+            user_profile.key(),
+            &mut user_profile.frozen,
+            frozen,
+        );
+
+        emit!(UserFrozenStatusChanged {
+            user: user_profile.user,
+            frozen,
+        });
+
+        Ok(())
+    }
+
+    /// Rewrite a subscription's metadata URI, leaving its status and dates alone (authority only)
+    pub fn update_subscription_metadata(
+        ctx: Context<UpdateSubscriptionMetadata>,
+        new_uri: String,
+    ) -> Result<()> {
+        require!(new_uri.len() <= MAX_METADATA_URI_LEN, SleekError::UriTooLong);
+
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.metadata_uri = new_uri;
+
+        emit!(SubscriptionMetadataUpdated {
+            user: subscription.user,
+            subscription_id: subscription.subscription_id,
+            metadata_uri: subscription.metadata_uri.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Allow or disallow a wallet to pay while the allowlist is enabled (authority only)
+    pub fn set_allowlisted(ctx: Context<SetAllowlisted>, allowlisted: bool) -> Result<()> {
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.user = ctx.accounts.user.key();
+        user_profile.bump = ctx.bumps.user_profile;
+        set_account_config(
+            ctx.accounts.authority.key(),
+            ConfigField::Allowlisted,
+            user_profile.key(),
+            &mut user_profile.allowlisted,
+            allowlisted,
+        );
+
+        emit!(AllowlistStatusChanged {
+            user: user_profile.user,
+            allowlisted,
+        });
+
+        Ok(())
+    }
+
+    /// Bring the state account up to the current layout version, growing it
+    /// if an older layout was smaller (authority only). A state still in the
+    /// first deployed layout needs `bonk_mint`, which that layout lacked
+    pub fn migrate_state(ctx: Context<MigrateState>) -> Result<()> {
+        let state_info = ctx.accounts.sleek_state.to_account_info();
+        let mut state = decode_state(
+            &state_info.try_borrow_data()?,
+            ctx.accounts.bonk_mint.as_ref().map(|mint| mint.key()),
+        )?;
+        require_keys_eq!(state.authority, ctx.accounts.authority.key(), SleekError::Unauthorized);
+
+        let from_version = state.version;
+        upgrade_state(&mut state)?;
+
+        let space = 8 + SleekState::INIT_SPACE;
+        if state_info.data_len() < space {
+            let top_up = Rent::get()?
+                .minimum_balance(space)
+                .saturating_sub(state_info.lamports());
+            if top_up > 0 {
+                let transfer_ctx = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: state_info.clone(),
+                    },
+                );
+                system_program::transfer(transfer_ctx, top_up)?;
+            }
+            state_info.realloc(space, true)?;
+        }
+        state.try_serialize(&mut &mut state_info.try_borrow_mut_data()?[..])?;
+
+        emit!(StateMigrated {
+            from_version,
+            to_version: state.version,
+        });
+
+        Ok(())
+    }
+
+    /// Pause or resume payments and redemptions (admin only)
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::Paused,
+            0,
+            &mut ctx.accounts.sleek_state.paused,
+            paused,
+        );
+        Ok(())
+    }
+
+    /// Pause or resume only redemptions, leaving payments running (admin only)
+    pub fn set_redemptions_paused(ctx: Context<UpdateConfig>, redemptions_paused: bool) -> Result<()> {
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::RedemptionsPaused,
+            0,
+            &mut ctx.accounts.sleek_state.redemptions_paused,
+            redemptions_paused,
+        );
+        Ok(())
+    }
+
+    /// Restrict payments to allowlisted wallets (admin only)
+    pub fn set_allowlist_enabled(ctx: Context<UpdateConfig>, allowlist_enabled: bool) -> Result<()> {
+        set_config(
+            ctx.accounts.authority.key(),
+            ConfigField::AllowlistEnabled,
+            0,
+            &mut ctx.accounts.sleek_state.allowlist_enabled,
+            allowlist_enabled,
+        );
+        Ok(())
+    }
+
+    /// Propose a new authority; it takes effect once accepted (authority only)
+    pub fn propose_authority(ctx: Context<ManageAdmins>, new_authority: Pubkey) -> Result<()> {
+        let sleek_state = &mut ctx.accounts.sleek_state;
+
+        emit!(ConfigKeyChanged {
+            field: ConfigField::PendingAuthority,
+            old_value: sleek_state.pending_authority.unwrap_or_default(),
+            new_value: new_authority,
+            admin: ctx.accounts.authority.key(),
+        });
+        sleek_state.pending_authority = Some(new_authority);
+
+        Ok(())
+    }
+
+    /// Let `admin` sign admin-only configuration instructions (authority only)
+    pub fn add_admin(ctx: Context<ManageAdmins>, admin: Pubkey) -> Result<()> {
+        let sleek_state = &mut ctx.accounts.sleek_state;
+
+        if sleek_state.is_admin(&admin) {
+            return Ok(());
+        }
+
+        require!(
+            sleek_state.admins.len() < MAX_ADMINS,
+            SleekError::AdminListFull
+        );
+        sleek_state.admins.push(admin);
+
+        emit!(AdminStatusChanged {
+            admin,
+            is_admin: true,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke a secondary admin (authority only)
+    pub fn remove_admin(ctx: Context<ManageAdmins>, admin: Pubkey) -> Result<()> {
+        let sleek_state = &mut ctx.accounts.sleek_state;
+
+        let position = sleek_state
+            .admins
+            .iter()
+            .position(|a| *a == admin)
+            .ok_or(SleekError::AdminNotFound)?;
+        sleek_state.admins.remove(position);
+
+        emit!(AdminStatusChanged {
+            admin,
+            is_admin: false,
+        });
+
+        Ok(())
+    }
+
+    /// Accept a pending authority transfer
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        
+        require!(
+            sleek_state.pending_authority == Some(ctx.accounts.new_authority.key()),
+            SleekError::Unauthorized
+        );
+
+        let old = sleek_state.authority;
+        sleek_state.authority = ctx.accounts.new_authority.key();
+        sleek_state.pending_authority = None;
+
+        emit!(AuthorityTransferred {
+            old,
+            new: sleek_state.authority,
+        });
+
+        Ok(())
+    }
+
+    /// Hand the BONK mint authority to a new key, e.g. ahead of an authority rotation (authority only)
+    pub fn set_mint_authority(
+        ctx: Context<SetMintAuthority>,
+        new_mint_authority: Pubkey,
+    ) -> Result<()> {
+        let authority_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::SetAuthority {
+                current_authority: ctx.accounts.authority.to_account_info(),
+                account_or_mint: ctx.accounts.bonk_mint.to_account_info(),
+            },
+        );
+        token::set_authority(authority_ctx, AuthorityType::MintTokens, Some(new_mint_authority))?;
+
+        emit!(MintAuthorityChanged {
+            old: ctx.accounts.authority.key(),
+            new: new_mint_authority,
+        });
+
+        Ok(())
+    }
+
+    /// Process subscription payment
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_subscription_payment(
+        ctx: Context<ProcessPayment>,
+        subscription_id: u64,
+        amount: u64,
+        sol_amount: u64,
+        tier: Tier,
+        referrer: Option<Pubkey>,
+        duration_secs: Option<i64>,
+        max_sol_amount: u64,
+        metadata_uri: String,
+        billing_period: BillingPeriod,
+        service_ref: Option<[u8; 32]>,
+        tag: String,
+    ) -> Result<()> {
+        require!(
+            subscription_id < AUTO_SUBSCRIPTION_ID_START,
+            SleekError::SubscriptionIdReserved
+        );
+
+        let request = PaymentRequest {
+            subscription_id,
+            amount,
+            sol_amount,
+            tier,
+            referrer,
+            duration_secs,
+            max_sol_amount,
+            metadata_uri,
+            billing_period,
+            cashback_to_apply: 0,
+            service_ref,
+            tag,
+        };
+        process_token_payment(ctx, request)
+    }
+
+    /// Process a subscription payment under the next globally unique
+    /// subscription id instead of one the client picks. `subscription_id` must
+    /// be `next_subscription_id`, which the subscription PDA is derived from;
+    /// returns the assigned id
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_subscription_payment_auto_id(
+        ctx: Context<ProcessPayment>,
+        subscription_id: u64,
+        amount: u64,
+        sol_amount: u64,
+        tier: Tier,
+        referrer: Option<Pubkey>,
+        duration_secs: Option<i64>,
+        max_sol_amount: u64,
+        metadata_uri: String,
+        billing_period: BillingPeriod,
+        service_ref: Option<[u8; 32]>,
+        tag: String,
+    ) -> Result<u64> {
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        require!(
+            subscription_id == sleek_state.next_subscription_id,
+            SleekError::SubscriptionIdMismatch
+        );
+        // An id picked before the range was reserved could already hold this PDA
+        require!(
+            ctx.accounts.subscription.user == Pubkey::default(),
+            SleekError::SubscriptionAlreadyExists
+        );
+        sleek_state.next_subscription_id = checked_add(subscription_id, 1)?;
+
+        let request = PaymentRequest {
+            subscription_id,
+            amount,
+            sol_amount,
+            tier,
+            referrer,
+            duration_secs,
+            max_sol_amount,
+            metadata_uri,
+            billing_period,
+            cashback_to_apply: 0,
+            service_ref,
+            tag,
+        };
+        process_token_payment(ctx, request)?;
+        Ok(subscription_id)
+    }
+
+    /// Process a subscription payment, burning up to `cashback_to_apply` of the
+    /// user's BONK for a discount at `bonk_per_lamport` BONK per unit owed
+    #[allow(clippy::too_many_arguments)]
+    pub fn pay_with_cashback(
+        ctx: Context<ProcessPayment>,
+        subscription_id: u64,
+        amount: u64,
+        sol_amount: u64,
+        tier: Tier,
+        referrer: Option<Pubkey>,
+        duration_secs: Option<i64>,
+        max_sol_amount: u64,
+        metadata_uri: String,
+        billing_period: BillingPeriod,
+        cashback_to_apply: u64,
+    ) -> Result<()> {
+        require!(cashback_to_apply > 0, SleekError::InvalidAmount);
+        require!(
+            subscription_id < AUTO_SUBSCRIPTION_ID_START,
+            SleekError::SubscriptionIdReserved
+        );
+
+        let request = PaymentRequest {
+            subscription_id,
+            amount,
+            sol_amount,
+            tier,
+            referrer,
+            duration_secs,
+            max_sol_amount,
+            metadata_uri,
+            billing_period,
+            cashback_to_apply,
+            service_ref: None,
+            tag: String::new(),
+        };
+        process_token_payment(ctx, request)
+    }
+
+    /// Process a subscription payment in native SOL instead of an SPL token
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_subscription_payment_sol(
+        ctx: Context<ProcessPaymentSol>,
+        subscription_id: u64,
+        amount: u64,
+        sol_amount: u64,
+        tier: Tier,
+        referrer: Option<Pubkey>,
+        duration_secs: Option<i64>,
+        max_sol_amount: u64,
+        metadata_uri: String,
+        billing_period: BillingPeriod,
+    ) -> Result<()> {
+        require!(
+            subscription_id < AUTO_SUBSCRIPTION_ID_START,
+            SleekError::SubscriptionIdReserved
+        );
+
+        let request = PaymentRequest {
+            subscription_id,
+            amount,
+            sol_amount,
+            tier,
+            referrer,
+            duration_secs,
+            max_sol_amount,
+            metadata_uri,
+            billing_period,
+            cashback_to_apply: 0,
+            service_ref: None,
+            tag: String::new(),
+        };
+
+        let now = clock::now(ctx.remaining_accounts)?;
+
+        // A plan fixes its own price; anything else must match the SOL/USD feed
+        if ctx.accounts.plan.is_none() {
+            oracle::check_sol_amount(
+                ctx.accounts.price_feed.as_deref(),
+                amount,
+                sol_amount,
+                &ctx.accounts.sleek_state,
+                now,
+            )?;
+        }
+
+        let mut accounts = PaymentAccounts {
+            payment: &mut ctx.accounts.payment,
+            subscription: &mut ctx.accounts.subscription,
+            user_profile: &mut ctx.accounts.user_profile,
+            subscription_index: &mut ctx.accounts.subscription_index,
+            sleek_state: &mut ctx.accounts.sleek_state,
+            user: &ctx.accounts.user,
+            user_bonk_account: &ctx.accounts.user_bonk_account,
+            bonk_mint: &ctx.accounts.bonk_mint,
+            referrer_bonk_account: ctx.accounts.referrer_bonk_account.as_ref(),
+            savings_bonk_account: ctx.accounts.savings_bonk_account.as_ref(),
+            coupon: ctx.accounts.coupon.as_mut(),
+            plan: ctx.accounts.plan.as_mut(),
+            authority: &ctx.accounts.authority,
+            token_program: &ctx.accounts.token_program,
+            payment_mint: native_mint::ID,
+            bumps: PaymentBumps {
+                payment: ctx.bumps.payment,
+                subscription: ctx.bumps.subscription,
+                user_profile: ctx.bumps.user_profile,
+                subscription_index: ctx.bumps.subscription_index,
+            },
+            now,
+        };
+        let terms = accounts.quote(&request)?;
+
+        let fee_recipient = ctx.accounts.fee_recipient.as_ref();
+        require!(
+            terms.fee_amount == 0 || fee_recipient.is_some(),
+            SleekError::InvalidFeeRecipient
+        );
+
+        // Record everything first so funds move and cashback mints only for a
+        // subscription that was actually created
+        let rewards = accounts.settle(&request, &terms)?;
+
+        if let Some(fee_recipient) = fee_recipient.filter(|_| terms.fee_amount > 0) {
+            let fee_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.user.to_account_info(),
+                    to: fee_recipient.to_account_info(),
+                },
+            );
+            system_program::transfer(fee_ctx, terms.fee_amount)?;
+        }
+
+        // Transfer lamports from user to authority
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.user.to_account_info(),
+                to: ctx.accounts.authority.to_account_info(),
+            },
+        );
+        system_program::transfer(transfer_ctx, terms.treasury_amount)?;
+
+        accounts.mint_rewards(&rewards)
+    }
+
+    /// Grant a new user one free subscription lasting `trial_secs`, with no
+    /// payment and no cashback
+    pub fn start_trial(ctx: Context<StartTrial>, subscription_id: u64) -> Result<()> {
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        let user_profile = &mut ctx.accounts.user_profile;
+
+        require!(!sleek_state.paused, SleekError::ProgramPaused);
+        require!(
+            subscription_id < AUTO_SUBSCRIPTION_ID_START,
+            SleekError::SubscriptionIdReserved
+        );
+        require!(sleek_state.trial_secs > 0, SleekError::TrialsDisabled);
+        require!(!user_profile.trial_used, SleekError::TrialAlreadyUsed);
+        require!(
+            !sleek_state.allowlist_enabled || user_profile.allowlisted,
+            SleekError::NotAllowlisted
+        );
+        user_profile.require_subscription_slot(sleek_state)?;
+
+        let now = clock::now(ctx.remaining_accounts)?;
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.user = ctx.accounts.user.key();
+        subscription.subscription_id = subscription_id;
+        subscription.amount = 0;
+        subscription.tier = Tier::Basic;
+        subscription.status = SubscriptionStatus::Active;
+        subscription.activation_date = now;
+        subscription.expiration_date = add_secs(now, sleek_state.trial_secs)?;
+        subscription.period_secs = sleek_state.trial_secs;
+        subscription.billing_period = BillingPeriod::Monthly;
+        subscription.bump = ctx.bumps.subscription;
+
+        user_profile.user = ctx.accounts.user.key();
+        user_profile.bump = ctx.bumps.user_profile;
+        user_profile.trial_used = true;
+        if user_profile.first_seen == 0 {
+            user_profile.first_seen = now;
+        }
+        user_profile.active_subscriptions = checked_add(user_profile.active_subscriptions, 1)?;
+
+        let subscription_index = &mut ctx.accounts.subscription_index;
+        subscription_index.user = ctx.accounts.user.key();
+        subscription_index.page = user_profile.index_page();
+        subscription_index.bump = ctx.bumps.subscription_index;
+        subscription_index.subscription_ids.push(subscription_id);
+        user_profile.indexed_subscriptions = checked_add(user_profile.indexed_subscriptions, 1)?;
+
+        sleek_state.total_subscriptions = checked_add(sleek_state.total_subscriptions, 1)?;
+        sleek_state.total_active_subscriptions =
+            checked_add(sleek_state.total_active_subscriptions, 1)?;
+
+        emit!(SubscriptionCreated {
+            user: ctx.accounts.user.key(),
+            subscription_id,
+            tier: subscription.tier,
+            activation_date: subscription.activation_date,
+            expiration_date: subscription.expiration_date,
+            billing_period: subscription.billing_period,
+            tag: subscription.tag.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Renew an existing subscription for another billing cycle
+    pub fn renew_subscription(
+        ctx: Context<RenewSubscription>,
+        sol_amount: u64,
+    ) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let sleek_state = &mut ctx.accounts.sleek_state;
+
+        require!(!sleek_state.paused, SleekError::ProgramPaused);
+        require!(sol_amount > 0, SleekError::InvalidAmount);
+        // Trials are free, so there is no cycle price to renew at
+        require!(subscription.sol_price > 0, SleekError::TrialNotRenewable);
+
+        require!(
+            !matches!(
+                subscription.status,
+                SubscriptionStatus::Cancelled | SubscriptionStatus::Paused
+            ),
+            SleekError::SubscriptionNotActive
+        );
+
+        // Cashback is earned on the full cycle, so a renewal has to pay for one
+        let terms = renewal_terms(
+            ctx.accounts.plan.as_ref(),
+            subscription,
+            ctx.accounts.user_token_account.mint,
+        )?;
+        require!(sol_amount >= terms.price, SleekError::RenewalUnderpaid);
+
+        // A clock reading before activation would skew the renewal math
+        let now = clock::now(ctx.remaining_accounts)?;
+        elapsed_secs(subscription.activation_date, now)?;
+
+        // Transfer renewal payment from user to the partner and the treasury
+        let treasury_amount = transfer_with_fee_split(
+            &ctx.accounts.token_program,
+            &ctx.accounts.user_token_account,
+            &ctx.accounts.authority_token_account,
+            ctx.accounts.fee_token_account.as_ref(),
+            ctx.accounts.user.to_account_info(),
+            &[],
+            sol_amount,
+            sleek_state,
+        )?;
+
+        // Calculate cashback at the tier's rate, limited by the user's cap
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.user = ctx.accounts.user.key();
+        user_profile.bump = ctx.bumps.user_profile;
+        user_profile.record_payment(now, sleek_state)?;
+
+        let cashback_bps = sleek_state.payment_cashback_bps(
+            terms.plan_cashback_bps,
+            subscription.tier,
+            user_profile.total_payments,
+            now,
+        );
+        let cashback_bps = user_profile.apply_winback(
+            cashback_bps,
+            subscription.subscription_id,
+            now,
+            sleek_state,
+        )?;
+        let cashback = calculate_payment_cashback(terms.amount, cashback_bps, sleek_state)?;
+        let mint_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::MintTo {
+                mint: ctx.accounts.bonk_mint.to_account_info(),
+                to: ctx.accounts.user_bonk_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        );
+        let cashback_amount = mint_cashback(
+            mint_ctx,
+            sleek_state,
+            Some(user_profile.total_cashback_earned),
+            ctx.accounts.user.key(),
+            cashback,
+            CashbackReason::Payment,
+        )?;
+
+        let previous_expiration_date = subscription.expiration_date;
+        let extend_from = sleek_state.renewal_start(previous_expiration_date, now)?;
+        let new_expiration_date = add_secs(extend_from, terms.period_secs)?;
+
+        let reactivated = subscription.status == SubscriptionStatus::Expired;
+        if reactivated {
+            transition(subscription.status, SubscriptionStatus::Active)?;
+            subscription.status = SubscriptionStatus::Active;
+        }
+        subscription.expiration_date = new_expiration_date;
+        subscription.amount_paid = checked_add(subscription.amount_paid, sol_amount)?;
+        subscription.treasury_paid = checked_add(subscription.treasury_paid, treasury_amount)?;
+        subscription.period_cashback = checked_add(subscription.period_cashback, cashback_amount)?;
+
+        // Update per-user stats
+        user_profile.total_spent = checked_add(user_profile.total_spent, sol_amount)?;
+        user_profile.total_payments = checked_add(user_profile.total_payments, 1)?;
+        user_profile.total_cashback_earned =
+            checked_add(user_profile.total_cashback_earned, cashback_amount)?;
+        if reactivated {
+            user_profile.require_subscription_slot(sleek_state)?;
+            update_plan_subscribers(ctx.accounts.plan.as_mut(), subscription, true)?;
+            user_profile.active_subscriptions = checked_add(user_profile.active_subscriptions, 1)?;
+            sleek_state.total_active_subscriptions =
+                checked_add(sleek_state.total_active_subscriptions, 1)?;
+        }
+
+        // Update global stats
+        sleek_state.total_payments = checked_add(sleek_state.total_payments, 1)?;
+
+        emit!(SubscriptionRenewed {
+            user: ctx.accounts.user.key(),
+            subscription_id: subscription.subscription_id,
+            cashback_amount,
+            previous_expiration_date,
+            new_expiration_date,
+        });
+
+        Ok(())
+    }
+
+    /// Pay part of a billing cycle, extending the subscription proportionally
+    pub fn installment_payment(
+        ctx: Context<RenewSubscription>,
+        sol_amount: u64,
+    ) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let sleek_state = &mut ctx.accounts.sleek_state;
+
+        require!(!sleek_state.paused, SleekError::ProgramPaused);
+        require!(sol_amount > 0, SleekError::InvalidAmount);
+        // Trials are free, so there is no cycle price to renew at
+        require!(subscription.sol_price > 0, SleekError::TrialNotRenewable);
+
+        require!(
+            !matches!(
+                subscription.status,
+                SubscriptionStatus::Cancelled | SubscriptionStatus::Paused
+            ),
+            SleekError::SubscriptionNotActive
+        );
+
+        // Credit the installment's share of a full cycle
+        let now = clock::now(ctx.remaining_accounts)?;
+        let terms = renewal_terms(
+            ctx.accounts.plan.as_ref(),
+            subscription,
+            ctx.accounts.user_token_account.mint,
+        )?;
+        let credited_secs = checked_mul_div(terms.period_secs as u64, sol_amount, terms.price)?;
+
+        let previous_expiration_date = subscription.expiration_date;
+        let extend_from = sleek_state.renewal_start(previous_expiration_date, now)?;
+        let new_expiration_date = add_secs(
+            extend_from,
+            i64::try_from(credited_secs).map_err(|_| error!(SleekError::ArithmeticOverflow))?,
+        )?;
+        require!(
+            new_expiration_date <= add_secs(now, MAX_SUBSCRIPTION_HORIZON)?,
+            SleekError::MaxDurationExceeded
+        );
+
+        let treasury_amount = transfer_with_fee_split(
+            &ctx.accounts.token_program,
+            &ctx.accounts.user_token_account,
+            &ctx.accounts.authority_token_account,
+            ctx.accounts.fee_token_account.as_ref(),
+            ctx.accounts.user.to_account_info(),
+            &[],
+            sol_amount,
+            sleek_state,
+        )?;
+
+        // Cashback accrues on the installment's share of the list price
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.user = ctx.accounts.user.key();
+        user_profile.bump = ctx.bumps.user_profile;
+        user_profile.record_payment(now, sleek_state)?;
+
+        let cashback = calculate_payment_cashback(
+            checked_mul_div(terms.amount, sol_amount, terms.price)?,
+            sleek_state.payment_cashback_bps(
+                terms.plan_cashback_bps,
+                subscription.tier,
+                user_profile.total_payments,
+                now,
+            ),
+            sleek_state,
+        )?;
+        let mint_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::MintTo {
+                mint: ctx.accounts.bonk_mint.to_account_info(),
+                to: ctx.accounts.user_bonk_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        );
+        let cashback_amount = mint_cashback(
+            mint_ctx,
+            sleek_state,
+            Some(user_profile.total_cashback_earned),
+            ctx.accounts.user.key(),
+            cashback,
+            CashbackReason::Payment,
+        )?;
+
+        let reactivated = subscription.status == SubscriptionStatus::Expired;
+        if reactivated {
+            transition(subscription.status, SubscriptionStatus::Active)?;
+            subscription.status = SubscriptionStatus::Active;
+        }
+        subscription.expiration_date = new_expiration_date;
+        subscription.amount_paid = checked_add(subscription.amount_paid, sol_amount)?;
+        subscription.treasury_paid = checked_add(subscription.treasury_paid, treasury_amount)?;
+        subscription.period_cashback = checked_add(subscription.period_cashback, cashback_amount)?;
+
+        // Update per-user stats
+        user_profile.total_spent = checked_add(user_profile.total_spent, sol_amount)?;
+        user_profile.total_payments = checked_add(user_profile.total_payments, 1)?;
+        user_profile.total_cashback_earned =
+            checked_add(user_profile.total_cashback_earned, cashback_amount)?;
+        if reactivated {
+            user_profile.require_subscription_slot(sleek_state)?;
+            update_plan_subscribers(ctx.accounts.plan.as_mut(), subscription, true)?;
+            user_profile.active_subscriptions = checked_add(user_profile.active_subscriptions, 1)?;
+            sleek_state.total_active_subscriptions =
+                checked_add(sleek_state.total_active_subscriptions, 1)?;
+        }
+
+        // Update global stats
+        sleek_state.total_payments = checked_add(sleek_state.total_payments, 1)?;
+
+        emit!(InstallmentPaid {
+            user: ctx.accounts.user.key(),
+            subscription_id: subscription.subscription_id,
+            sol_amount,
+            amount_paid: subscription.amount_paid,
+            cashback_amount,
+            new_expiration_date,
+        });
+
+        Ok(())
+    }
+
+    /// Redeem BONK cashback
+    pub fn redeem_cashback(
+        ctx: Context<RedeemCashback>,
+        amount: u64,
+    ) -> Result<()> {
+        let redemption = &mut ctx.accounts.redemption;
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        
+        require!(!sleek_state.paused, SleekError::ProgramPaused);
+        require!(!sleek_state.redemptions_paused, SleekError::RedemptionsPaused);
+        require!(amount > 0, SleekError::InvalidAmount);
+        require!(amount >= sleek_state.min_redemption_amount, SleekError::RedemptionTooSmall);
+        require!(
+            amount <= ctx.accounts.user_bonk_account.amount,
+            SleekError::InsufficientBalance
+        );
+
+        let now = clock::now(ctx.remaining_accounts)?;
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.user = ctx.accounts.user.key();
+        user_profile.bump = ctx.bumps.user_profile;
+        require!(!user_profile.frozen, SleekError::AccountFrozen);
+
+        // Throttle repeated redemptions
+        if sleek_state.redemption_cooldown_secs > 0 && user_profile.last_redemption_at > 0 {
+            require!(
+                now >= add_secs(user_profile.last_redemption_at, sleek_state.redemption_cooldown_secs)?,
+                SleekError::RedemptionCooldown
+            );
+        }
+        user_profile.last_redemption_at = now;
+
+        // Set redemption details
+        redemption.user = ctx.accounts.user.key();
+        redemption.amount = amount;
+        redemption.timestamp = now;
+        redemption.bump = ctx.bumps.redemption;
+
+        // Burn BONK tokens from user
+        let burn_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Burn {
+                mint: ctx.accounts.bonk_mint.to_account_info(),
+                from: ctx.accounts.user_bonk_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        );
+        token::burn(burn_ctx, amount)?;
+
+        sleek_state.redemption_count = checked_add(sleek_state.redemption_count, 1)?;
+        user_profile.redemption_count = checked_add(user_profile.redemption_count, 1)?;
+
+        // Read the balance back so indexers can reconcile without another RPC
+        ctx.accounts.user_bonk_account.reload()?;
+
+        emit!(CashbackRedeemed {
+            user: ctx.accounts.user.key(),
+            amount,
+            remaining_balance: ctx.accounts.user_bonk_account.amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Mint BONK to a user without a payment, e.g. when migrating balances (authority only)
+    pub fn airdrop_cashback(ctx: Context<AirdropCashback>, amount: u64) -> Result<()> {
+        let sleek_state = &mut ctx.accounts.sleek_state;
+
+        require!(amount > 0, SleekError::InvalidAmount);
+
+        let amount = limit_cashback(amount, None, sleek_state)?;
+        let total_airdropped = checked_add(sleek_state.total_airdropped, amount)?;
+        require!(
+            total_airdropped <= sleek_state.max_airdrop_total,
+            SleekError::AirdropCeilingExceeded
+        );
+
+        let mint_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::MintTo {
+                mint: ctx.accounts.bonk_mint.to_account_info(),
+                to: ctx.accounts.recipient_bonk_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        );
+        mint_cashback(
+            mint_ctx,
+            sleek_state,
+            None,
+            ctx.accounts.recipient_bonk_account.owner,
+            amount,
+            CashbackReason::Airdrop,
+        )?;
+
+        let airdrop = &mut ctx.accounts.airdrop;
+        airdrop.user = ctx.accounts.recipient_bonk_account.owner;
+        airdrop.amount = amount;
+        airdrop.timestamp = clock::now(ctx.remaining_accounts)?;
+        airdrop.bump = ctx.bumps.airdrop;
+
+        sleek_state.total_airdropped = total_airdropped;
+        sleek_state.airdrop_count = checked_add(sleek_state.airdrop_count, 1)?;
+
+        emit!(AirdropMinted {
+            user: airdrop.user,
+            amount,
+            total_airdropped,
+        });
+
+        Ok(())
+    }
+
+    /// Transfer BONK cashback to another user
+    pub fn gift_cashback(ctx: Context<GiftCashback>, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.sleek_state.paused, SleekError::ProgramPaused);
+        require!(amount > 0, SleekError::InvalidAmount);
+        require!(
+            amount <= ctx.accounts.sender_bonk_account.amount,
+            SleekError::InsufficientBalance
+        );
+
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.user = ctx.accounts.user.key();
+        user_profile.bump = ctx.bumps.user_profile;
+        require!(!user_profile.frozen, SleekError::AccountFrozen);
+
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.sender_bonk_account.to_account_info(),
+                to: ctx.accounts.recipient_bonk_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        );
+        token::transfer(transfer_ctx, amount)?;
+
+        let gift = &mut ctx.accounts.gift;
+        gift.from = ctx.accounts.user.key();
+        gift.to = ctx.accounts.recipient_bonk_account.owner;
+        gift.amount = amount;
+        gift.timestamp = clock::now(ctx.remaining_accounts)?;
+        gift.bump = ctx.bumps.gift;
+
+        user_profile.gift_count = checked_add(user_profile.gift_count, 1)?;
+
+        emit!(CashbackGifted {
+            from: gift.from,
+            to: gift.to,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel subscription
+    pub fn cancel_subscription(
+        ctx: Context<CancelSubscription>,
+        reason: Option<CancellationReason>,
+    ) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        
+        require!(
+            subscription.user == ctx.accounts.user.key(),
+            SleekError::Unauthorized
+        );
+        
+        transition(subscription.status, SubscriptionStatus::Cancelled)?;
+        subscription.status = SubscriptionStatus::Cancelled;
+        let now = clock::now(ctx.remaining_accounts)?;
+        subscription.cancellation_date = Some(now);
+        let reason = reason.unwrap_or(CancellationReason::Other);
+        subscription.cancellation_reason = Some(reason);
+        update_plan_subscribers(ctx.accounts.plan.as_mut(), subscription, false)?;
+
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.active_subscriptions = checked_sub(user_profile.active_subscriptions, 1)?;
+        user_profile.last_cancelled_at = now;
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        sleek_state.total_active_subscriptions =
+            checked_sub(sleek_state.total_active_subscriptions, 1)?;
+
+        emit!(SubscriptionCancelled {
+            user: ctx.accounts.user.key(),
+            subscription_id: subscription.subscription_id,
+            refund_amount: 0,
+            refund_issued: false,
+            reason,
+            cashback_clawed_back: 0,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel subscription and refund the unused portion of the billing period.
+    /// Past the refund window the cancellation still goes through, unrefunded
+    pub fn cancel_subscription_with_refund(
+        ctx: Context<CancelSubscriptionWithRefund>,
+    ) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        
+        require!(
+            subscription.user == ctx.accounts.user.key(),
+            SleekError::Unauthorized
+        );
+        
+        transition(subscription.status, SubscriptionStatus::Cancelled)?;
+
+        let now = clock::now(ctx.remaining_accounts)?;
+        let refund_window_secs = ctx.accounts.sleek_state.refund_window_secs;
+        let refund_amount = if elapsed_secs(subscription.activation_date, now)? <= refund_window_secs
+            && refund_window_secs > 0
+        {
+            // The partner's fee never reached the treasury, so only its share is prorated
+            calculate_refund(
+                subscription.treasury_paid,
+                subscription.activation_date,
+                subscription.expiration_date,
+                now,
+            )?
+        } else {
+            0
+        };
+
+        // Transfer the refund from authority back to user
+        if refund_amount > 0 {
+            let transfer_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.authority_token_account.to_account_info(),
+                    to: ctx.accounts.user_token_account.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            );
+            token::transfer(transfer_ctx, refund_amount)?;
+        }
+
+        // Claw back the cashback earned on the refunded share, as far as the balance allows
+        let cashback_clawed_back = if refund_amount > 0 {
+            checked_mul_div(
+                subscription.period_cashback,
+                refund_amount,
+                subscription.treasury_paid,
+            )?
+            .min(ctx.accounts.user_bonk_account.amount)
+        } else {
+            0
+        };
+        if cashback_clawed_back > 0 {
+            let burn_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    mint: ctx.accounts.bonk_mint.to_account_info(),
+                    from: ctx.accounts.user_bonk_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            );
+            token::burn(burn_ctx, cashback_clawed_back)?;
+        }
+
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        sleek_state.total_refunded = checked_add(sleek_state.total_refunded, refund_amount)?;
+
+        subscription.status = SubscriptionStatus::Cancelled;
+        subscription.cancellation_date = Some(now);
+        subscription.cancellation_reason = Some(CancellationReason::Other);
+        update_plan_subscribers(ctx.accounts.plan.as_mut(), subscription, false)?;
+
+        // A refunded period is over, so it cannot be reactivated and refunded again
+        if refund_amount > 0 {
+            ctx.accounts.payment.status = PaymentStatus::Refunded;
+            subscription.amount_paid = checked_sub(subscription.amount_paid, refund_amount)?;
+            subscription.treasury_paid = checked_sub(subscription.treasury_paid, refund_amount)?;
+            subscription.period_cashback =
+                checked_sub(subscription.period_cashback, cashback_clawed_back)?;
+            subscription.expiration_date = now;
+        }
+
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.active_subscriptions = checked_sub(user_profile.active_subscriptions, 1)?;
+        user_profile.last_cancelled_at = now;
+        sleek_state.total_active_subscriptions =
+            checked_sub(sleek_state.total_active_subscriptions, 1)?;
+
+        emit!(SubscriptionCancelled {
+            user: ctx.accounts.user.key(),
+            subscription_id: subscription.subscription_id,
+            refund_amount,
+            refund_issued: refund_amount > 0,
+            reason: CancellationReason::Other,
+            cashback_clawed_back,
+        });
+
+        Ok(())
+    }
+
+    /// Reactivate a cancelled subscription while its paid period is still running
+    pub fn reactivate_subscription(ctx: Context<ReactivateSubscription>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+
+        transition(subscription.status, SubscriptionStatus::Active)?;
+
+        // Once the paid period has lapsed the user must renew instead
+        require!(
+            clock::now(ctx.remaining_accounts)? < subscription.expiration_date,
+            SleekError::SubscriptionExpired
+        );
+
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.require_subscription_slot(&ctx.accounts.sleek_state)?;
+
+        subscription.status = SubscriptionStatus::Active;
+        subscription.cancellation_date = None;
+        subscription.cancellation_reason = None;
+        update_plan_subscribers(ctx.accounts.plan.as_mut(), subscription, true)?;
+
+        user_profile.active_subscriptions = checked_add(user_profile.active_subscriptions, 1)?;
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        sleek_state.total_active_subscriptions =
+            checked_add(sleek_state.total_active_subscriptions, 1)?;
+
+        emit!(SubscriptionReactivated {
+            user: ctx.accounts.user.key(),
+            subscription_id: subscription.subscription_id,
+            expiration_date: subscription.expiration_date,
+        });
+
+        Ok(())
+    }
+
+    /// Put an active subscription on hold, freezing its remaining paid time
+    pub fn pause_subscription(ctx: Context<PauseSubscription>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+
+        transition(subscription.status, SubscriptionStatus::Paused)?;
+
+        let now = clock::now(ctx.remaining_accounts)?;
+        require!(now < subscription.expiration_date, SleekError::SubscriptionExpired);
+
+        subscription.status = SubscriptionStatus::Paused;
+        subscription.paused_at = Some(now);
+
+        emit!(SubscriptionPaused {
+            user: ctx.accounts.user.key(),
+            subscription_id: subscription.subscription_id,
+            paused_at: now,
+        });
+
+        Ok(())
+    }
+
+    /// Lift a hold, pushing the expiration back by the time spent paused
+    pub fn resume_subscription(ctx: Context<PauseSubscription>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+
+        require!(
+            subscription.status == SubscriptionStatus::Paused,
+            SleekError::SubscriptionNotPaused
+        );
+        transition(subscription.status, SubscriptionStatus::Active)?;
+
+        let now = clock::now(ctx.remaining_accounts)?;
+        let paused_at = subscription.paused_at.ok_or(SleekError::SubscriptionNotPaused)?;
+        let paused_secs = elapsed_secs(paused_at, now)?;
+
+        subscription.status = SubscriptionStatus::Active;
+        subscription.expiration_date = add_secs(subscription.expiration_date, paused_secs)?;
+        subscription.paused_at = None;
+
+        emit!(SubscriptionResumed {
+            user: ctx.accounts.user.key(),
+            subscription_id: subscription.subscription_id,
+            paused_secs,
+            expiration_date: subscription.expiration_date,
+        });
+
+        Ok(())
+    }
+
+    /// Choose the share of future payment cashback locked in the user's savings
+    /// vault, creating the vault on first use
+    pub fn set_savings_split(ctx: Context<SetSavingsSplit>, savings_split_bps: u16) -> Result<()> {
+        require!(
+            savings_split_bps as u64 <= BPS_DENOMINATOR,
+            SleekError::InvalidSavingsSplit
+        );
+
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.user = ctx.accounts.user.key();
+        user_profile.bump = ctx.bumps.user_profile;
+        user_profile.savings_split_bps = savings_split_bps;
+
+        emit!(SavingsSplitChanged {
+            user: user_profile.user,
+            savings_split_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Move everything in the user's savings vault to their spendable BONK
+    /// account once the lock has elapsed
+    pub fn withdraw_savings(ctx: Context<WithdrawSavings>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        require!(
+            now >= ctx.accounts.user_profile.savings_unlock_at,
+            SleekError::SavingsLocked
+        );
+
+        let amount = ctx.accounts.savings_bonk_account.amount;
+        require!(amount > 0, SleekError::InvalidAmount);
+
+        let sleek_state = &ctx.accounts.sleek_state;
+        let sleek_state_seeds: &[&[u8]] = &[b"sleek_state", &[sleek_state.bump]];
+        let signer_seeds = &[sleek_state_seeds];
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.savings_bonk_account.to_account_info(),
+                to: ctx.accounts.user_bonk_account.to_account_info(),
+                authority: sleek_state.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::transfer(transfer_ctx, amount)?;
+
+        emit!(SavingsWithdrawn {
+            user: ctx.accounts.user.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Turn automatic renewal by the keeper on or off
+    pub fn set_auto_renew(ctx: Context<SetAutoRenew>, enabled: bool) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+
+        require!(
+            subscription.user == ctx.accounts.user.key(),
+            SleekError::Unauthorized
+        );
+
+        subscription.auto_renew = enabled;
+
+        emit!(AutoRenewChanged {
+            subscription_id: subscription.subscription_id,
+            enabled,
+        });
+
+        Ok(())
+    }
+
+    /// Set the subscription's accounting tag, e.g. a cost center or PO number
+    pub fn set_subscription_tag(ctx: Context<SetSubscriptionTag>, tag: String) -> Result<()> {
+        require!(tag.len() <= MAX_TAG_LEN, SleekError::TagTooLong);
+
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.tag = tag;
+
+        emit!(SubscriptionTagUpdated {
+            user: subscription.user,
+            subscription_id: subscription.subscription_id,
+            tag: subscription.tag.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Transfer an active subscription to another wallet
+    ///
+    /// Subscriptions are seeded by their owner, so the data moves to a new PDA
+    /// under `new_owner` and the old account is closed back to the sender. The
+    /// NFT and index entry move with it; a recurring authorization is closed,
+    /// since it charges the sender's payment account.
+    pub fn transfer_subscription(ctx: Context<TransferSubscription>, new_owner: Pubkey) -> Result<()> {
+        let subscription = &ctx.accounts.subscription;
+
+        require!(
+            subscription.user == ctx.accounts.user.key(),
+            SleekError::Unauthorized
+        );
+        require!(
+            subscription.status == SubscriptionStatus::Active,
+            SleekError::SubscriptionNotActive
+        );
+        require!(
+            clock::now(ctx.remaining_accounts)? < subscription.expiration_date,
+            SleekError::SubscriptionExpired
+        );
+
+        let subscription_id = subscription.subscription_id;
+
+        // The NFT goes wherever the subscription goes
+        if let Some(nft_mint) = subscription.nft_mint {
+            let (user_nft_account, new_owner_nft_account) = match (
+                ctx.accounts.user_nft_account.as_ref(),
+                ctx.accounts.new_owner_nft_account.as_ref(),
+            ) {
+                (Some(from), Some(to)) => (from, to),
+                _ => return err!(SleekError::NftAccountMissing),
+            };
+            require!(
+                user_nft_account.mint == nft_mint && new_owner_nft_account.mint == nft_mint,
+                SleekError::InvalidMint
+            );
+            let transfer_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: user_nft_account.to_account_info(),
+                    to: new_owner_nft_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            );
+            token::transfer(transfer_ctx, 1)?;
+        }
+
+        // Recurring charges and refunds draw on what the previous owner
+        // authorized and paid, so neither carries over
+        ctx.accounts.new_subscription.set_inner(Subscription {
+            user: new_owner,
+            auto_renew: false,
+            last_payment: Pubkey::default(),
+            bump: ctx.bumps.new_subscription,
+            ..(**subscription).clone()
+        });
+        close_recurring(&ctx.accounts.recurring, &ctx.accounts.user.to_account_info())?;
+
+        // Move the id from the previous owner's index to the new owner's
+        remove_indexed_subscription(&mut ctx.accounts.user_subscription_index, subscription_id)?;
+
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.active_subscriptions = checked_sub(user_profile.active_subscriptions, 1)?;
+
+        let new_owner_profile = &mut ctx.accounts.new_owner_profile;
+        new_owner_profile.require_subscription_slot(&ctx.accounts.sleek_state)?;
+        new_owner_profile.user = new_owner;
+        new_owner_profile.bump = ctx.bumps.new_owner_profile;
+        new_owner_profile.active_subscriptions =
+            checked_add(new_owner_profile.active_subscriptions, 1)?;
+
+        let new_owner_subscription_index = &mut ctx.accounts.new_owner_subscription_index;
+        new_owner_subscription_index.user = new_owner;
+        new_owner_subscription_index.page = new_owner_profile.index_page();
+        new_owner_subscription_index.bump = ctx.bumps.new_owner_subscription_index;
+        new_owner_subscription_index.subscription_ids.push(subscription_id);
+        new_owner_profile.indexed_subscriptions =
+            checked_add(new_owner_profile.indexed_subscriptions, 1)?;
+
+        emit!(SubscriptionTransferred {
+            from: ctx.accounts.user.key(),
+            to: new_owner,
+            subscription_id: ctx.accounts.new_subscription.subscription_id,
+        });
+
+        Ok(())
+    }
+
+    /// Move an active subscription onto another plan mid-cycle. The unused value
+    /// of the current cycle is credited toward a fresh cycle of the new plan;
+    /// any shortfall is charged and any excess refunded
+    pub fn change_plan(ctx: Context<ChangePlan>, new_plan_id: u64) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let new_plan = &mut ctx.accounts.new_plan;
+        let now = clock::now(ctx.remaining_accounts)?;
+
+        require!(!ctx.accounts.sleek_state.paused, SleekError::ProgramPaused);
+        require!(
+            subscription.status == SubscriptionStatus::Active,
+            SleekError::SubscriptionNotActive
+        );
+        require!(now < subscription.expiration_date, SleekError::SubscriptionExpired);
+        elapsed_secs(subscription.activation_date, now)?;
+        require!(new_plan.active, SleekError::PlanInactive);
+        require!(
+            new_plan.payment_mint == ctx.accounts.user_token_account.mint,
+            SleekError::UnsupportedPaymentMint
+        );
+        require!(subscription.plan_id != Some(new_plan_id), SleekError::PlanUnchanged);
+
+        // Only what the treasury kept of the current cycle is credited
+        let proration_credit = calculate_refund(
+            subscription.treasury_paid,
+            subscription.activation_date,
+            subscription.expiration_date,
+            now,
+        )?;
+        // The unused share of the period's cashback moves with the credit
+        let carried_cashback = calculate_refund(
+            subscription.period_cashback,
+            subscription.activation_date,
+            subscription.expiration_date,
+            now,
+        )?;
+        let charge = new_plan.price.saturating_sub(proration_credit);
+        let refund = proration_credit.saturating_sub(new_plan.price);
+
+        let charge_treasury_amount = if charge > 0 {
+            transfer_with_fee_split(
+                &ctx.accounts.token_program,
+                &ctx.accounts.user_token_account,
+                &ctx.accounts.authority_token_account,
+                ctx.accounts.fee_token_account.as_ref(),
+                ctx.accounts.user.to_account_info(),
+                &[],
+                charge,
+                &mut ctx.accounts.sleek_state,
+            )?
+        } else {
+            0
+        };
+        if refund > 0 {
+            let transfer_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.authority_token_account.to_account_info(),
+                    to: ctx.accounts.user_token_account.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            );
+            token::transfer(transfer_ctx, refund)?;
+        }
+
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        sleek_state.total_refunded = checked_add(sleek_state.total_refunded, refund)?;
+
+        // Leave the old plan's count before the reference moves to the new one
+        let from = subscription.plan_id;
+        update_plan_subscribers(ctx.accounts.current_plan.as_mut(), subscription, false)?;
+        new_plan.active_subscribers = checked_add(new_plan.active_subscribers, 1)?;
+        new_plan.lifetime_subscribers = checked_add(new_plan.lifetime_subscribers, 1)?;
+
+        subscription.plan_id = Some(new_plan_id);
+        subscription.tier = new_plan.tier;
+        subscription.amount = new_plan.price;
+        subscription.sol_price = new_plan.price;
+        // Credit plus charge less refund is exactly the new plan's price
+        subscription.amount_paid = new_plan.price;
+        subscription.treasury_paid =
+            checked_add(checked_sub(proration_credit, refund)?, charge_treasury_amount)?;
+        subscription.period_cashback = carried_cashback;
+        subscription.activation_date = now;
+        subscription.expiration_date = add_secs(now, new_plan.duration_secs)?;
+        subscription.period_secs = new_plan.duration_secs;
+        subscription.billing_period = BillingPeriod::Monthly;
+
+        emit!(PlanChanged {
+            user: subscription.user,
+            subscription_id: subscription.subscription_id,
+            from,
+            to: new_plan_id,
+            proration_credit,
+            amount_charged: charge,
+            amount_refunded: refund,
+        });
+
+        Ok(())
+    }
+
+    /// Mark a lapsed subscription as expired (permissionless crank)
+    pub fn expire_subscription(ctx: Context<ExpireSubscription>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let accounts = ctx.accounts;
+        mark_expired(
+            &mut accounts.subscription,
+            &mut accounts.user_profile,
+            &mut accounts.sleek_state,
+            accounts.plan.as_mut(),
+            now,
+        )
+    }
+
+    /// Expire a lapsed subscription and pay the keeper `keeper_reward` BONK
+    /// (permissionless crank)
+    ///
+    /// Rewards come from the pre-minted keeper vault since the BONK mint
+    /// authority does not co-sign keeper transactions.
+    pub fn expire_and_reward(ctx: Context<ExpireAndReward>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let accounts = ctx.accounts;
+        mark_expired(
+            &mut accounts.subscription,
+            &mut accounts.user_profile,
+            &mut accounts.sleek_state,
+            accounts.plan.as_mut(),
+            now,
+        )?;
+
+        let sleek_state = &mut accounts.sleek_state;
+        let reward = sleek_state.keeper_reward;
+        if reward == 0 {
+            return Ok(());
+        }
+        require!(
+            reward <= sleek_state.keeper_reward_budget,
+            SleekError::KeeperBudgetExhausted
+        );
+        sleek_state.keeper_reward_budget = checked_sub(sleek_state.keeper_reward_budget, reward)?;
+
+        let sleek_state_seeds: &[&[u8]] = &[b"sleek_state", &[sleek_state.bump]];
+        let signer_seeds = &[sleek_state_seeds];
+        let transfer_ctx = CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.keeper_vault.to_account_info(),
+                to: accounts.keeper_bonk_account.to_account_info(),
+                authority: sleek_state.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::transfer(transfer_ctx, reward)?;
+
+        emit!(KeeperRewarded {
+            keeper: accounts.keeper.key(),
+            user: accounts.subscription.user,
+            subscription_id: accounts.subscription.subscription_id,
+            amount: reward,
+        });
+
+        Ok(())
+    }
+
+    /// Mint BONK into the keeper vault and add it to the reward budget (authority only)
+    pub fn fund_keeper_rewards(ctx: Context<FundKeeperRewards>, amount: u64) -> Result<()> {
+        require!(amount > 0, SleekError::InvalidAmount);
+
+        let mint_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::MintTo {
+                mint: ctx.accounts.bonk_mint.to_account_info(),
+                to: ctx.accounts.keeper_vault.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        );
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        let amount = mint_cashback(
+            mint_ctx,
+            sleek_state,
+            None,
+            ctx.accounts.keeper_vault.key(),
+            amount,
+            CashbackReason::KeeperReward,
+        )?;
+        sleek_state.keeper_reward_budget = checked_add(sleek_state.keeper_reward_budget, amount)?;
+        Ok(())
+    }
+
+    /// Close an old payment record and return its rent to the user
+    pub fn close_payment(ctx: Context<ClosePayment>) -> Result<()> {
+        let payment = &ctx.accounts.payment;
+        let closer = ctx.accounts.closer.key();
+
+        require!(
+            closer == payment.user || closer == ctx.accounts.sleek_state.authority,
+            SleekError::Unauthorized
+        );
+
+        require!(
+            checked_sub(clock::now(ctx.remaining_accounts)?, payment.timestamp)?
+                >= ctx.accounts.sleek_state.payment_retention_secs,
+            SleekError::PaymentTooRecent
+        );
+
+        emit!(PaymentClosed {
+            user: payment.user,
+            payment: payment.key(),
+            subscription_id: payment.subscription_id,
+        });
+
+        Ok(())
+    }
+
+    /// Close an ended subscription and return its rent to the owner
+    ///
+    /// A subscription that lapsed past its grace period without being cranked
+    /// is expired first, so the active counters stay in step. Its recurring
+    /// authorization is closed and its id leaves the owner's index.
+    pub fn close_subscription(ctx: Context<CloseSubscription>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let accounts = ctx.accounts;
+        let effective = accounts
+            .subscription
+            .effective_status(now, accounts.sleek_state.grace_period_secs);
+        require!(
+            effective != SubscriptionStatus::Active && effective != SubscriptionStatus::Paused,
+            SleekError::SubscriptionStillActive
+        );
+
+        if accounts.subscription.status == SubscriptionStatus::Active {
+            mark_expired(
+                &mut accounts.subscription,
+                &mut accounts.user_profile,
+                &mut accounts.sleek_state,
+                accounts.plan.as_mut(),
+                now,
+            )?;
+        }
+
+        // A re-purchase of the id recreates this PDA, so nothing may point at it
+        close_recurring(&accounts.recurring, &accounts.user.to_account_info())?;
+        remove_indexed_subscription(
+            &mut accounts.subscription_index,
+            accounts.subscription.subscription_id,
+        )?;
+
+        emit!(SubscriptionClosed {
+            user: accounts.subscription.user,
+            subscription_id: accounts.subscription.subscription_id,
+            status: accounts.subscription.status,
+        });
+
+        Ok(())
+    }
+
+    /// Mark a payment's fulfillment intent as provisioned (authority only)
+    pub fn mark_fulfilled(ctx: Context<MarkFulfilled>) -> Result<()> {
+        let intent = &mut ctx.accounts.fulfillment_intent;
+        require!(!intent.fulfilled, SleekError::IntentAlreadyFulfilled);
+
+        intent.fulfilled = true;
+        intent.fulfilled_at = clock::now(ctx.remaining_accounts)?;
+
+        emit!(Fulfilled {
+            user: intent.user,
+            payment: intent.payment,
+            subscription_id: intent.subscription_id,
+            service_ref: intent.service_ref,
+        });
+
+        Ok(())
+    }
+
+    /// Refund what the treasury kept of a payment, e.g. after a dispute, and end
+    /// the period it opened (authority only)
+    ///
+    /// Cashback minted for the payment is left with the user, since burning it
+    /// would need the user's signature.
+    pub fn refund_payment(ctx: Context<RefundPayment>) -> Result<()> {
+        let payment = &mut ctx.accounts.payment;
+
+        require!(
+            payment.status == PaymentStatus::Completed,
+            SleekError::AlreadyRefunded
+        );
+
+        // The partner's fee never reached the treasury, so only the rest comes back
+        let refund_amount = checked_sub(payment.sol_amount, payment.fee_amount)?;
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.authority_token_account.to_account_info(),
+                to: ctx.accounts.user_token_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        );
+        token::transfer(transfer_ctx, refund_amount)?;
+
+        payment.status = PaymentStatus::Refunded;
+
+        let sleek_state = &mut ctx.accounts.sleek_state;
+        sleek_state.total_refunded = checked_add(sleek_state.total_refunded, refund_amount)?;
+
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.total_spent = user_profile.total_spent.saturating_sub(refund_amount);
+
+        // Refunding the payment that opened the current period ends it; an older
+        // payment's period is already over and later renewals stay paid for
+        let now = clock::now(ctx.remaining_accounts)?;
+        let subscription = &mut ctx.accounts.subscription;
+        if payment.key() == subscription.last_payment {
+            if !matches!(
+                subscription.status,
+                SubscriptionStatus::Cancelled | SubscriptionStatus::Expired
+            ) {
+                end_subscription(
+                    subscription,
+                    user_profile,
+                    sleek_state,
+                    ctx.accounts.plan.as_mut(),
+                )?;
+            }
+            subscription.expiration_date = subscription.expiration_date.min(now);
+            subscription.amount_paid = subscription.amount_paid.saturating_sub(refund_amount);
+            subscription.treasury_paid = subscription.treasury_paid.saturating_sub(refund_amount);
+        }
+
+        emit!(PaymentRefunded {
+            user: payment.user,
+            payment: payment.key(),
+            subscription_id: payment.subscription_id,
+            amount: refund_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Pre-authorize a keeper to charge the subscription for up to `cycles` cycles
+    ///
+    /// The Sleek state PDA is approved as delegate on the user's payment
+    /// account for `max_per_cycle * cycles`.
+    pub fn authorize_recurring(
+        ctx: Context<AuthorizeRecurring>,
+        max_per_cycle: u64,
+        cycles: u32,
+    ) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+
+        require!(max_per_cycle > 0 && cycles > 0, SleekError::InvalidAmount);
+        require!(subscription.sol_price > 0, SleekError::TrialNotRenewable);
+        require!(
+            !matches!(
+                subscription.status,
+                SubscriptionStatus::Cancelled | SubscriptionStatus::Paused
+            ),
+            SleekError::SubscriptionNotActive
+        );
+
+        let allowance = checked_mul(max_per_cycle, cycles as u64)?;
+        let approve_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Approve {
+                to: ctx.accounts.user_token_account.to_account_info(),
+                delegate: ctx.accounts.sleek_state.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        );
+        token::approve(approve_ctx, allowance)?;
+
+        let recurring = &mut ctx.accounts.recurring;
+        recurring.user = ctx.accounts.user.key();
+        recurring.subscription_id = subscription.subscription_id;
+        recurring.payment_account = ctx.accounts.user_token_account.key();
+        recurring.max_per_cycle = max_per_cycle;
+        recurring.cycles_remaining = cycles;
+        recurring.next_charge_at = subscription.expiration_date;
+        recurring.bump = ctx.bumps.recurring;
+
+        // Authorizing charges signals the intent to renew automatically
+        subscription.auto_renew = true;
+
+        emit!(RecurringAuthorized {
+            user: recurring.user,
+            subscription_id: recurring.subscription_id,
+            max_per_cycle,
+            cycles,
+            next_charge_at: recurring.next_charge_at,
+        });
+
+        Ok(())
+    }
+
+    /// Charge a pre-authorized cycle once it is due (permissionless crank)
+    ///
+    /// No cashback is minted here since the BONK mint authority does not
+    /// co-sign keeper transactions.
+    pub fn charge_recurring(ctx: Context<ChargeRecurring>) -> Result<()> {
+        let recurring = &mut ctx.accounts.recurring;
+        let subscription = &mut ctx.accounts.subscription;
+        let sleek_state = &mut ctx.accounts.sleek_state;
+
+        require!(!sleek_state.paused, SleekError::ProgramPaused);
+        require!(recurring.cycles_remaining > 0, SleekError::RecurringExhausted);
+        require!(subscription.auto_renew, SleekError::AutoRenewDisabled);
+        require!(subscription.sol_price > 0, SleekError::TrialNotRenewable);
+        require!(
+            !matches!(
+                subscription.status,
+                SubscriptionStatus::Cancelled | SubscriptionStatus::Paused
+            ),
+            SleekError::SubscriptionNotActive
+        );
+
+        let now = clock::now(ctx.remaining_accounts)?;
+        require!(now >= recurring.next_charge_at, SleekError::ChargeNotDue);
+
+        let terms = renewal_terms(
+            ctx.accounts.plan.as_ref(),
+            subscription,
+            ctx.accounts.payment_account.mint,
+        )?;
+        let sol_amount = terms.price;
+        require!(
+            sol_amount <= recurring.max_per_cycle,
+            SleekError::RecurringLimitExceeded
+        );
+
+        // A charge the payment account can no longer cover is recorded instead
+        // of reverted, so the user can be told before the subscription ends
+        let payment_account = &ctx.accounts.payment_account;
+        let failure = if !payment_account.delegate.contains(&sleek_state.key())
+            || payment_account.delegated_amount < sol_amount
+        {
+            Some(RenewalFailureReason::DelegateRevoked)
+        } else if payment_account.amount < sol_amount {
+            Some(RenewalFailureReason::InsufficientFunds)
+        } else {
+            None
+        };
+        if let Some(reason) = failure {
+            // Back off so repeated cranks cannot burn through the failure limit at once
+            recurring.next_charge_at = add_secs(now, sleek_state.renewal_retry_secs)?;
+            subscription.renewal_failures = checked_add(subscription.renewal_failures, 1)?;
+            emit!(RenewalFailed {
+                user: recurring.user,
+                subscription_id: recurring.subscription_id,
+                reason,
+                attempt: subscription.renewal_failures,
+            });
+
+            let max_failures = sleek_state.max_renewal_failures as u32;
+            if max_failures > 0
+                && subscription.renewal_failures >= max_failures
+                && subscription.status == SubscriptionStatus::Active
+            {
+                end_subscription(
+                    subscription,
+                    &mut ctx.accounts.user_profile,
+                    sleek_state,
+                    ctx.accounts.plan.as_mut(),
+                )?;
+            }
+            return Ok(());
+        }
+
+        // Pull the cycle's price using the delegated allowance
+        let sleek_state_seeds: &[&[u8]] = &[b"sleek_state", &[sleek_state.bump]];
+        let signer_seeds = &[sleek_state_seeds];
+        let treasury_amount = transfer_with_fee_split(
+            &ctx.accounts.token_program,
+            &ctx.accounts.payment_account,
+            &ctx.accounts.authority_token_account,
+            ctx.accounts.fee_token_account.as_ref(),
+            sleek_state.to_account_info(),
+            signer_seeds,
+            sol_amount,
+            sleek_state,
+        )?;
+
+        let extend_from = sleek_state.renewal_start(subscription.expiration_date, now)?;
+        let new_expiration_date = add_secs(extend_from, terms.period_secs)?;
+
+        let reactivated = subscription.status == SubscriptionStatus::Expired;
+        if reactivated {
+            transition(subscription.status, SubscriptionStatus::Active)?;
+            subscription.status = SubscriptionStatus::Active;
+        }
+        subscription.expiration_date = new_expiration_date;
+        subscription.amount_paid = checked_add(subscription.amount_paid, sol_amount)?;
+        subscription.treasury_paid = checked_add(subscription.treasury_paid, treasury_amount)?;
+        subscription.renewal_failures = 0;
+
+        recurring.cycles_remaining = checked_sub(recurring.cycles_remaining, 1)?;
+        recurring.next_charge_at = new_expiration_date;
+
+        // Update per-user stats
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.record_payment(now, sleek_state)?;
+        user_profile.total_spent = checked_add(user_profile.total_spent, sol_amount)?;
+        user_profile.total_payments = checked_add(user_profile.total_payments, 1)?;
+        if reactivated {
+            user_profile.require_subscription_slot(sleek_state)?;
+            update_plan_subscribers(ctx.accounts.plan.as_mut(), subscription, true)?;
+            user_profile.active_subscriptions = checked_add(user_profile.active_subscriptions, 1)?;
+            sleek_state.total_active_subscriptions =
+                checked_add(sleek_state.total_active_subscriptions, 1)?;
+        }
+
+        sleek_state.total_payments = checked_add(sleek_state.total_payments, 1)?;
+
+        emit!(RecurringCharged {
+            user: recurring.user,
+            subscription_id: recurring.subscription_id,
+            amount: sol_amount,
+            cycles_remaining: recurring.cycles_remaining,
+            next_charge_at: recurring.next_charge_at,
+        });
+
+        Ok(())
+    }
+
+    /// Mint a 1-of-1 NFT with Metaplex metadata representing a subscription
+    pub fn mint_subscription_nft(ctx: Context<MintSubscriptionNft>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+
+        require!(subscription.nft_mint.is_none(), SleekError::NftAlreadyMinted);
+
+        let sleek_state_seeds: &[&[u8]] = &[b"sleek_state", &[ctx.accounts.sleek_state.bump]];
+        let signer_seeds = &[sleek_state_seeds];
+
+        // Mint the single token to the user
+        let mint_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::MintTo {
+                mint: ctx.accounts.nft_mint.to_account_info(),
+                to: ctx.accounts.user_nft_account.to_account_info(),
+                authority: ctx.accounts.sleek_state.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::mint_to(mint_ctx, 1)?;
+
+        // Attach Metaplex metadata
+        let metadata_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_metadata_program.to_account_info(),
+            CreateMetadataAccountsV3 {
+                metadata: ctx.accounts.metadata.to_account_info(),
+                mint: ctx.accounts.nft_mint.to_account_info(),
+                mint_authority: ctx.accounts.sleek_state.to_account_info(),
+                payer: ctx.accounts.user.to_account_info(),
+                update_authority: ctx.accounts.sleek_state.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+            },
+            signer_seeds,
+        );
+        metadata::create_metadata_accounts_v3(
+            metadata_ctx,
+            DataV2 {
+                name: format!("Sleek Subscription #{}", subscription.subscription_id),
+                symbol: SUBSCRIPTION_NFT_SYMBOL.to_string(),
+                uri: format!("{}{}", SUBSCRIPTION_NFT_URI_BASE, subscription.subscription_id),
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            },
+            true,
+            true,
+            None,
+        )?;
+
+        // Remove the mint authority so the supply is fixed at one
+        let authority_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::SetAuthority {
+                current_authority: ctx.accounts.sleek_state.to_account_info(),
+                account_or_mint: ctx.accounts.nft_mint.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::set_authority(authority_ctx, AuthorityType::MintTokens, None)?;
+
+        subscription.nft_mint = Some(ctx.accounts.nft_mint.key());
+
+        emit!(SubscriptionNftMinted {
+            user: ctx.accounts.user.key(),
+            subscription_id: subscription.subscription_id,
+            nft_mint: ctx.accounts.nft_mint.key(),
+        });
+
+        Ok(())
+    }
+
+    // View instructions hand their result back as Anchor return data
+    // (`set_return_data`), which is authoritative and what `.view()` decodes.
+    // The balance and subscription reads also emit an event carrying the same
+    // value for clients that only read simulation logs.
+
+    /// Get user's cashback balance
+    pub fn get_cashback_balance(ctx: Context<GetCashbackBalance>) -> Result<u64> {
+        let user_bonk_account = &ctx.accounts.user_bonk_account;
+
+        emit!(CashbackBalanceRead {
+            user: user_bonk_account.owner,
+            balance: user_bonk_account.amount,
+        });
+
+        Ok(user_bonk_account.amount)
+    }
+
+    /// Get the user's loyalty tier (0 = Bronze, 1 = Silver, 2 = Gold)
+    pub fn get_loyalty_tier(ctx: Context<GetLoyaltyTier>) -> Result<u8> {
+        Ok(ctx
+            .accounts
+            .sleek_state
+            .loyalty_tier(ctx.accounts.user_profile.total_payments))
+    }
+
+    /// Get a subscription's status, accounting for expiration
+    pub fn get_subscription_status(
+        ctx: Context<GetSubscriptionStatus>,
+    ) -> Result<SubscriptionStatusInfo> {
+        let subscription = &ctx.accounts.subscription;
+        let now = clock::now(ctx.remaining_accounts)?;
+
+        let info = SubscriptionStatusInfo {
+            status: subscription.effective_status(now, ctx.accounts.sleek_state.grace_period_secs),
+            expiration_date: subscription.expiration_date,
+            seconds_remaining: subscription.expiration_date.saturating_sub(now).max(0),
+        };
+
+        emit!(SubscriptionStatusRead {
+            user: subscription.user,
+            subscription_id: subscription.subscription_id,
+            status: info.status,
+            expiration_date: info.expiration_date,
+            seconds_remaining: info.seconds_remaining,
+        });
+
+        Ok(info)
+    }
+
+    /// Get what a subscription entitles its holder to, for gating access by
+    /// simulation; an invalid subscription is entitled to no features
+    pub fn check_entitlement(ctx: Context<CheckEntitlement>) -> Result<Entitlement> {
+        let subscription = &ctx.accounts.subscription;
+        let sleek_state = &ctx.accounts.sleek_state;
+        let now = clock::now(ctx.remaining_accounts)?;
+
+        let features = match (&ctx.accounts.plan, subscription.plan_id) {
+            (None, None) => sleek_state.tier_features[subscription.tier.index()],
+            (Some(plan), Some(plan_id)) if plan.plan_id == plan_id => plan.features,
+            _ => return err!(SleekError::PlanMismatch),
+        };
+        let valid = subscription.is_valid(now, sleek_state.grace_period_secs);
+        let entitlement = Entitlement {
+            valid,
+            tier: subscription.tier,
+            seconds_remaining: if valid {
+                subscription.expiration_date.saturating_sub(now).max(0)
+            } else {
+                0
+            },
+            features_bitmask: if valid { features } else { 0 },
+        };
+
+        emit!(EntitlementRead {
+            user: subscription.user,
+            subscription_id: subscription.subscription_id,
+            valid: entitlement.valid,
+            features_bitmask: entitlement.features_bitmask,
+        });
+
+        Ok(entitlement)
+    }
+
+    /// Get the whole days left on a subscription, zero once it has ended
+    pub fn get_days_remaining(ctx: Context<GetSubscriptionStatus>) -> Result<i64> {
+        let subscription = &ctx.accounts.subscription;
+        let now = clock::now(ctx.remaining_accounts)?;
+
+        let days_remaining = if subscription.is_valid(now, ctx.accounts.sleek_state.grace_period_secs) {
+            subscription.expiration_date.saturating_sub(now).max(0) / SECONDS_PER_DAY
+        } else {
+            0
+        };
+
+        emit!(DaysRemainingRead {
+            user: subscription.user,
+            subscription_id: subscription.subscription_id,
+            days_remaining,
+        });
+
+        Ok(days_remaining)
+    }
+
+    /// Whether a subscription grants access right now: active and not lapsed
+    /// past the grace period
+    pub fn is_subscription_valid(ctx: Context<GetSubscriptionStatus>) -> Result<bool> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        Ok(ctx
+            .accounts
+            .subscription
+            .is_valid(now, ctx.accounts.sleek_state.grace_period_secs))
+    }
+
+    /// Get a payment's status
+    pub fn get_payment_status(ctx: Context<GetPaymentStatus>) -> Result<PaymentStatus> {
+        Ok(ctx.accounts.payment.status.clone())
+    }
+
+    /// Get how many subscribers a plan has now and has had overall
+    pub fn get_plan_stats(ctx: Context<GetPlanStats>) -> Result<PlanStats> {
+        let plan = &ctx.accounts.plan;
+
+        Ok(PlanStats {
+            active_subscribers: plan.active_subscribers,
+            lifetime_subscribers: plan.lifetime_subscribers,
+        })
+    }
+
+    /// Get how much the authority has collected and refunded
+    pub fn get_treasury_stats(ctx: Context<GetTreasuryStats>) -> Result<TreasuryStats> {
+        let sleek_state = &ctx.accounts.sleek_state;
+
+        Ok(TreasuryStats {
+            total_collected: sleek_state.total_collected,
+            total_refunded: sleek_state.total_refunded,
+            net_collected: sleek_state
+                .total_collected
+                .saturating_sub(sleek_state.total_refunded),
+        })
+    }
+
+    /// Get the program-wide totals the admin dashboard shows in one read
+    pub fn get_stats(ctx: Context<GetStats>) -> Result<SleekStats> {
+        let sleek_state = &ctx.accounts.sleek_state;
+
+        Ok(SleekStats {
+            total_subscriptions: sleek_state.total_subscriptions,
+            total_active_subscriptions: sleek_state.total_active_subscriptions,
+            total_payments: sleek_state.total_payments,
+            total_cashback_minted: sleek_state.total_cashback_minted,
+            total_collected: sleek_state.total_collected,
+            total_refunded: sleek_state.total_refunded,
+            cashback_bps: sleek_state.cashback_bps,
+        })
+    }
+}
+
+/// Cashback owed on a payment at `cashback_bps`, computed with a u128 intermediate
+fn calculate_cashback(amount: u64, cashback_bps: u16) -> Result<u64> {
+    checked_mul_div(amount, cashback_bps as u64, BPS_DENOMINATOR)
+}
+
+/// `amount` reduced by `discount_bps`
+fn apply_discount(amount: u64, discount_bps: u16) -> Result<u64> {
+    checked_sub(amount, checked_mul_div(amount, discount_bps as u64, BPS_DENOMINATOR)?)
+}
+
+/// Cashback on a payment in cashback-mint units, raised to the configured
+/// floor but never above `amount`
+fn calculate_payment_cashback(amount: u64, cashback_bps: u16, sleek_state: &SleekState) -> Result<u64> {
+    let cashback = sleek_state.to_cashback_units(calculate_cashback(amount, cashback_bps)?)?;
+    if amount == 0 {
+        return Ok(cashback);
+    }
+    Ok(cashback.max(sleek_state.min_cashback.min(sleek_state.to_cashback_units(amount)?)))
+}
+
+/// Check plan terms before creating or updating a plan
+fn validate_plan(name: &str, price: u64, duration_secs: i64, cashback_bps: u16) -> Result<()> {
+    require!(
+        !name.is_empty() && name.len() <= MAX_PLAN_NAME_LEN,
+        SleekError::InvalidPlanConfig
+    );
+    require!(price > 0, SleekError::InvalidPlanConfig);
+    require!(
+        duration_secs > 0 && duration_secs <= MAX_SUBSCRIPTION_HORIZON,
+        SleekError::InvalidPlanConfig
+    );
+    require!(cashback_bps <= MAX_CASHBACK_BPS, SleekError::InvalidPlanConfig);
+    Ok(())
+}
+
+/// Set a plan's price, duration and cashback rate, logging each change by `admin`
+fn set_plan_terms(
+    admin: Pubkey,
+    plan: &mut Account<Plan>,
+    price: u64,
+    duration_secs: i64,
+    cashback_bps: u16,
+) {
+    let account = plan.key();
+    set_account_config(admin, ConfigField::PlanPrice, account, &mut plan.price, price);
+    set_account_config(
+        admin,
+        ConfigField::PlanDurationSecs,
+        account,
+        &mut plan.duration_secs,
+        duration_secs,
+    );
+    set_account_config(
+        admin,
+        ConfigField::PlanCashbackBps,
+        account,
+        &mut plan.cashback_bps,
+        cashback_bps,
+    );
+}
+
+/// Pay for a subscription in an SPL token, shared by the plain and cashback-discounted paths
+fn process_token_payment(ctx: Context<ProcessPayment>, request: PaymentRequest) -> Result<()> {
+    let now = clock::now(ctx.remaining_accounts)?;
+
+    // Wrapped SOL is checked against the SOL/USD feed unless a plan fixes the price
+    if ctx.accounts.plan.is_none() && ctx.accounts.user_token_account.mint == native_mint::ID {
+        oracle::check_sol_amount(
+            ctx.accounts.price_feed.as_deref(),
+            request.amount,
+            request.sol_amount,
+            &ctx.accounts.sleek_state,
+            now,
+        )?;
+    }
+
+    let mut accounts = PaymentAccounts {
+        payment: &mut ctx.accounts.payment,
+        subscription: &mut ctx.accounts.subscription,
+        user_profile: &mut ctx.accounts.user_profile,
+        subscription_index: &mut ctx.accounts.subscription_index,
+        sleek_state: &mut ctx.accounts.sleek_state,
+        user: &ctx.accounts.user,
+        user_bonk_account: &ctx.accounts.user_bonk_account,
+        bonk_mint: &ctx.accounts.bonk_mint,
+        referrer_bonk_account: ctx.accounts.referrer_bonk_account.as_ref(),
+        savings_bonk_account: ctx.accounts.savings_bonk_account.as_ref(),
+        coupon: ctx.accounts.coupon.as_mut(),
+        plan: ctx.accounts.plan.as_mut(),
+        authority: &ctx.accounts.authority,
+        token_program: &ctx.accounts.token_program,
+        payment_mint: ctx.accounts.user_token_account.mint,
+        bumps: PaymentBumps {
+            payment: ctx.bumps.payment,
+            subscription: ctx.bumps.subscription,
+            user_profile: ctx.bumps.user_profile,
+            subscription_index: ctx.bumps.subscription_index,
+        },
+        now,
+    };
+    let terms = accounts.quote(&request)?;
+
+    require!(
+        ctx.accounts.user_token_account.amount >= terms.sol_amount,
+        SleekError::InsufficientBalance
+    );
+    let fee_token_account = ctx.accounts.fee_token_account.as_ref();
+    require!(
+        terms.fee_amount == 0 || fee_token_account.is_some(),
+        SleekError::InvalidFeeRecipient
+    );
+
+    // Record everything first so funds move and cashback mints only for a
+    // subscription that was actually created
+    let rewards = accounts.settle(&request, &terms)?;
+
+    if let Some(fee_token_account) = fee_token_account.filter(|_| terms.fee_amount > 0) {
+        let fee_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user_token_account.to_account_info(),
+                to: fee_token_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        );
+        token::transfer(fee_ctx, terms.fee_amount)?;
+    }
+
+    // Transfer SOL from user to authority
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.user_token_account.to_account_info(),
+            to: ctx.accounts.authority_token_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        },
+    );
+    token::transfer(transfer_ctx, terms.treasury_amount)?;
+
+    accounts.burn_applied_cashback(&request, &terms)?;
+    accounts.mint_rewards(&rewards)?;
+
+    // Leave a marker the backend polls to provision the service off-chain
+    match (request.service_ref, ctx.accounts.fulfillment_intent.as_mut()) {
+        (None, None) => {}
+        (Some(service_ref), Some(intent)) => {
+            intent.user = ctx.accounts.user.key();
+            intent.payment = ctx.accounts.payment.key();
+            intent.subscription_id = request.subscription_id;
+            intent.service_ref = service_ref;
+            intent.fulfilled = false;
+            intent.created_at = ctx.accounts.payment.timestamp;
+            intent.bump = ctx.bumps.fulfillment_intent;
+        }
+        _ => return err!(SleekError::IntentMismatch),
+    }
+
+    Ok(())
+}
+
+/// Move a repeat charge of `amount` out of `from`, paying the partner its
+/// configured share and the treasury the rest, as a payment's quote does
+#[allow(clippy::too_many_arguments)]
+fn transfer_with_fee_split<'info>(
+    token_program: &Program<'info, Token>,
+    from: &Account<'info, TokenAccount>,
+    treasury_token_account: &Account<'info, TokenAccount>,
+    fee_token_account: Option<&Account<'info, TokenAccount>>,
+    authority: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+    sleek_state: &mut SleekState,
+) -> Result<u64> {
+    let (fee_amount, treasury_amount) = sleek_state.split_fee(amount)?;
+    let transfer = |to: AccountInfo<'info>, amount: u64| {
+        let transfer_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer { from: from.to_account_info(), to, authority: authority.clone() },
+            signer_seeds,
+        );
+        token::transfer(transfer_ctx, amount)
+    };
+
+    if fee_amount > 0 {
+        let fee_token_account =
+            fee_token_account.ok_or(error!(SleekError::InvalidFeeRecipient))?;
+        transfer(fee_token_account.to_account_info(), fee_amount)?;
+        emit!(PaymentSplit {
+            user: from.owner,
+            fee_recipient: sleek_state.fee_recipient,
+            fee_amount,
+            treasury_amount,
+        });
+    }
+    transfer(treasury_token_account.to_account_info(), treasury_amount)?;
+    sleek_state.total_collected = checked_add(sleek_state.total_collected, treasury_amount)?;
+    Ok(treasury_amount)
+}
+
+/// What one renewal cycle costs and buys
+struct RenewalTerms {
+    period_secs: i64,
+    /// Price of a full cycle in the payment mint
+    price: u64,
+    /// List amount cashback accrues on for a full cycle
+    amount: u64,
+    plan_cashback_bps: Option<u16>,
+}
+
+/// Terms of a renewal paid in `payment_mint`: a plan's current terms when the
+/// subscription was bought through one, otherwise the cycle bought at purchase
+fn renewal_terms(
+    plan: Option<&Account<Plan>>,
+    subscription: &Subscription,
+    payment_mint: Pubkey,
+) -> Result<RenewalTerms> {
+    match (plan, subscription.plan_id) {
+        (None, None) => Ok(RenewalTerms {
+            period_secs: subscription.period_secs,
+            price: subscription.sol_price,
+            amount: subscription.amount,
+            plan_cashback_bps: None,
+        }),
+        (Some(plan), Some(plan_id)) if plan.plan_id == plan_id => {
+            require!(plan.active, SleekError::PlanInactive);
+            require!(plan.payment_mint == payment_mint, SleekError::UnsupportedPaymentMint);
+            Ok(RenewalTerms {
+                period_secs: plan.duration_secs,
+                price: plan.price,
+                amount: plan.price,
+                plan_cashback_bps: Some(plan.cashback_bps),
+            })
+        }
+        _ => err!(SleekError::PlanMismatch),
+    }
+}
+
+/// Move a subscription into or out of its plan's active subscriber count.
+/// The plan account must be passed exactly when the subscription has one
+fn update_plan_subscribers(
+    plan: Option<&mut Account<Plan>>,
+    subscription: &Subscription,
+    joined: bool,
+) -> Result<()> {
+    match (plan, subscription.plan_id) {
+        (None, None) => Ok(()),
+        (Some(plan), Some(plan_id)) if plan.plan_id == plan_id => {
+            plan.active_subscribers = if joined {
+                checked_add(plan.active_subscribers, 1)?
+            } else {
+                checked_sub(plan.active_subscribers, 1)?
+            };
+            Ok(())
+        }
+        _ => err!(SleekError::PlanMismatch),
+    }
+}
+
+/// Expire a subscription whose paid period and grace window have both passed
+fn mark_expired(
+    subscription: &mut Subscription,
+    user_profile: &mut UserProfile,
+    sleek_state: &mut SleekState,
+    plan: Option<&mut Account<Plan>>,
+    now: i64,
+) -> Result<()> {
+    transition(subscription.status, SubscriptionStatus::Expired)?;
+
+    elapsed_secs(subscription.activation_date, now)?;
+    require!(
+        now > add_secs(subscription.expiration_date, sleek_state.grace_period_secs)?,
+        SleekError::NotYetExpired
+    );
+
+    end_subscription(subscription, user_profile, sleek_state, plan)
+}
+
+/// Move an active subscription to `Expired`, dropping it from every active count
+fn end_subscription(
+    subscription: &mut Subscription,
+    user_profile: &mut UserProfile,
+    sleek_state: &mut SleekState,
+    plan: Option<&mut Account<Plan>>,
+) -> Result<()> {
+    transition(subscription.status, SubscriptionStatus::Expired)?;
+
+    subscription.status = SubscriptionStatus::Expired;
+    update_plan_subscribers(plan, subscription, false)?;
+
+    user_profile.active_subscriptions = checked_sub(user_profile.active_subscriptions, 1)?;
+    sleek_state.total_active_subscriptions =
+        checked_sub(sleek_state.total_active_subscriptions, 1)?;
+
+    emit!(SubscriptionExpired {
+        user: subscription.user,
+        subscription_id: subscription.subscription_id,
+        expiration_date: subscription.expiration_date,
+    });
+
+    Ok(())
+}
+
+/// Close a subscription's recurring authorization, if one was ever created,
+/// returning its rent to `receiver`
+fn close_recurring<'info>(recurring: &AccountInfo<'info>, receiver: &AccountInfo<'info>) -> Result<()> {
+    if recurring.data_is_empty() {
+        return Ok(());
+    }
+    let refunded_lamports = checked_add(receiver.lamports(), recurring.lamports())?;
+    **receiver.try_borrow_mut_lamports()? = refunded_lamports;
+    **recurring.try_borrow_mut_lamports()? = 0;
+    recurring.assign(&system_program::ID);
+    recurring.realloc(0, false)?;
+    Ok(())
+}
+
+/// Drop a subscription id from the index page that lists it
+fn remove_indexed_subscription(
+    subscription_index: &mut SubscriptionIndex,
+    subscription_id: u64,
+) -> Result<()> {
+    let position = subscription_index
+        .subscription_ids
+        .iter()
+        .position(|id| *id == subscription_id)
+        .ok_or(SleekError::SubscriptionNotIndexed)?;
+    subscription_index.subscription_ids.remove(position);
+    Ok(())
+}
+
+/// Unused share of `amount` for the remainder of the period, zero once expired
+fn calculate_refund(amount: u64, activation_date: i64, expiration_date: i64, now: i64) -> Result<u64> {
+    if now >= expiration_date || expiration_date <= activation_date {
+        return Ok(0);
+    }
+
+    let remaining = checked_sub(expiration_date, now.max(activation_date))?;
+    let total = checked_sub(expiration_date, activation_date)?;
+    checked_mul_div(amount, remaining as u64, total as u64)
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SleekState::INIT_SPACE,
+        seeds = [b"sleek_state"],
+        bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    pub bonk_mint: Account<'info, Mint>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateState<'info> {
+    /// CHECK: may still hold an older layout, so it is decoded and checked in `migrate_state`
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump,
+        owner = crate::ID
+    )]
+    pub sleek_state: UncheckedAccount<'info>,
+    
+    /// Only required for the first deployed layout, which predates `bonk_mint`
+    pub bonk_mint: Option<Account<'info, Mint>>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        constraint = sleek_state.is_admin(&authority.key()) @ SleekError::Unauthorized
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    /// The primary authority or any secondary admin
+    pub authority: Signer<'info>,
+}
+
+/// Settings that move funds or change what is minted stay with the primary
+/// authority; secondary admins only get the operational ones in `UpdateConfig`
+#[derive(Accounts)]
+pub struct UpdateTreasuryConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = authority @ SleekError::Unauthorized
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ManageAdmins<'info> {
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = authority @ SleekError::Unauthorized
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetUserFrozen<'info> {
+    #[account(
+        mut,
+        seeds = [b"profile", user_profile.user.as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = authority @ SleekError::Unauthorized
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSubscriptionMetadata<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription", subscription.user.as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = authority @ SleekError::Unauthorized
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAllowlisted<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"profile", user.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    /// CHECK: only used to derive the profile address
+    pub user: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = authority @ SleekError::Unauthorized
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMintAuthority<'info> {
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = authority @ SleekError::Unauthorized,
+        has_one = bonk_mint @ SleekError::InvalidMint
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(mut)]
+    pub bonk_mint: Account<'info, Mint>,
+    
+    pub authority: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(code_hash: [u8; 32])]
+pub struct CreateCoupon<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Coupon::INIT_SPACE,
+        seeds = [b"coupon", code_hash.as_ref()],
+        bump
+    )]
+    pub coupon: Account<'info, Coupon>,
+    
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = authority @ SleekError::Unauthorized
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(plan_id: u64)]
+pub struct CreatePlan<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Plan::INIT_SPACE,
+        seeds = [b"plan", plan_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub plan: Account<'info, Plan>,
+    
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = authority @ SleekError::Unauthorized
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePlan<'info> {
+    #[account(
+        mut,
+        seeds = [b"plan", plan.plan_id.to_le_bytes().as_ref()],
+        bump = plan.bump
+    )]
+    pub plan: Account<'info, Plan>,
+    
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = authority @ SleekError::Unauthorized
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(subscription_id: u64)]
+pub struct ProcessPayment<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Payment::INIT_SPACE,
+        seeds = [b"payment", user.key().as_ref(), &sleek_state.total_payments.to_le_bytes()],
+        bump
+    )]
+    pub payment: Account<'info, Payment>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + Subscription::INIT_SPACE,
+        seeds = [b"subscription", user.key().as_ref(), &subscription_id.to_le_bytes()],
+        bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"profile", user.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + SubscriptionIndex::INIT_SPACE,
+        seeds = [
+            b"subscription_index",
+            user.key().as_ref(),
+            &user_profile.index_page().to_le_bytes()
+        ],
+        bump
+    )]
+    pub subscription_index: Account<'info, SubscriptionIndex>,
+    
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = sleek_state.allowed_payment_mints.contains(&user_token_account.mint)
+            @ SleekError::UnsupportedPaymentMint,
+        constraint = user_token_account.owner == user.key() @ SleekError::InvalidTokenAccountOwner
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = authority_token_account.mint == user_token_account.mint
+            @ SleekError::UnsupportedPaymentMint,
+        constraint = authority_token_account.owner == sleek_state.authority
+            @ SleekError::Unauthorized
+    )]
+    pub authority_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == sleek_state.fee_recipient
+            @ SleekError::InvalidFeeRecipient,
+        constraint = fee_token_account.mint == user_token_account.mint
+            @ SleekError::UnsupportedPaymentMint
+    )]
+    pub fee_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = user_bonk_account.owner == user.key() @ SleekError::InvalidTokenAccountOwner
+    )]
+    pub user_bonk_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = bonk_mint.key() == sleek_state.bonk_mint @ SleekError::InvalidMint,
+        // Never mint cashback in the token being paid with
+        constraint = bonk_mint.key() != user_token_account.mint @ SleekError::InvalidMint,
+        constraint = bonk_mint.key() != authority_token_account.mint @ SleekError::InvalidMint
+    )]
+    pub bonk_mint: Account<'info, Mint>,
+    
+    /// Only required when a referral bonus is paid
+    #[account(mut)]
+    pub referrer_bonk_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Only required when the user locks part of their cashback in savings
+    #[account(
+        mut,
+        seeds = [b"savings", user.key().as_ref()],
+        bump
+    )]
+    pub savings_bonk_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"coupon", coupon.code_hash.as_ref()],
+        bump = coupon.bump
+    )]
+    pub coupon: Option<Account<'info, Coupon>>,
+    
+    #[account(
+        mut,
+        seeds = [b"plan", plan.plan_id.to_le_bytes().as_ref()],
+        bump = plan.bump
+    )]
+    pub plan: Option<Account<'info, Plan>>,
+    
+    /// Only required when the payment carries a `service_ref`
+    #[account(
+        init,
+        payer = user,
+        space = 8 + FulfillmentIntent::INIT_SPACE,
+        seeds = [b"intent", payment.key().as_ref()],
+        bump
+    )]
+    pub fulfillment_intent: Option<Account<'info, FulfillmentIntent>>,
+    
+    /// CHECK: decoded and validated as a Pyth price update in `oracle::read_price`;
+    /// only required while SOL prices are checked
+    pub price_feed: Option<UncheckedAccount<'info>>,
+    
+    #[account(constraint = authority.key() == sleek_state.authority @ SleekError::Unauthorized)]
+    pub authority: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(subscription_id: u64)]
+pub struct ProcessPaymentSol<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Payment::INIT_SPACE,
+        seeds = [b"payment", user.key().as_ref(), &sleek_state.total_payments.to_le_bytes()],
+        bump
+    )]
+    pub payment: Account<'info, Payment>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + Subscription::INIT_SPACE,
+        seeds = [b"subscription", user.key().as_ref(), &subscription_id.to_le_bytes()],
+        bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"profile", user.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + SubscriptionIndex::INIT_SPACE,
+        seeds = [
+            b"subscription_index",
+            user.key().as_ref(),
+            &user_profile.index_page().to_le_bytes()
+        ],
+        bump
+    )]
+    pub subscription_index: Account<'info, SubscriptionIndex>,
+    
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    /// Only required when a fee split is configured
+    #[account(
+        mut,
+        address = sleek_state.fee_recipient @ SleekError::InvalidFeeRecipient
+    )]
+    pub fee_recipient: Option<SystemAccount<'info>>,
+    
+    #[account(
+        mut,
+        constraint = user_bonk_account.owner == user.key() @ SleekError::InvalidTokenAccountOwner
+    )]
+    pub user_bonk_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = bonk_mint.key() == sleek_state.bonk_mint @ SleekError::InvalidMint
+    )]
+    pub bonk_mint: Account<'info, Mint>,
+    
+    /// Only required when a referral bonus is paid
+    #[account(mut)]
+    pub referrer_bonk_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Only required when the user locks part of their cashback in savings
+    #[account(
+        mut,
+        seeds = [b"savings", user.key().as_ref()],
+        bump
+    )]
+    pub savings_bonk_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"coupon", coupon.code_hash.as_ref()],
+        bump = coupon.bump
+    )]
+    pub coupon: Option<Account<'info, Coupon>>,
+    
+    #[account(
+        mut,
+        seeds = [b"plan", plan.plan_id.to_le_bytes().as_ref()],
+        bump = plan.bump
+    )]
+    pub plan: Option<Account<'info, Plan>>,
+    
+    /// CHECK: decoded and validated as a Pyth price update in `oracle::read_price`;
+    /// only required while SOL prices are checked
+    pub price_feed: Option<UncheckedAccount<'info>>,
+    
+    #[account(
+        mut,
+        constraint = authority.key() == sleek_state.authority @ SleekError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(subscription_id: u64)]
+pub struct StartTrial<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Subscription::INIT_SPACE,
+        seeds = [b"subscription", user.key().as_ref(), &subscription_id.to_le_bytes()],
+        bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"profile", user.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + SubscriptionIndex::INIT_SPACE,
+        seeds = [
+            b"subscription_index",
+            user.key().as_ref(),
+            &user_profile.index_page().to_le_bytes()
+        ],
+        bump
+    )]
+    pub subscription_index: Account<'info, SubscriptionIndex>,
+    
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RenewSubscription<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription", user.key().as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"profile", user.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = sleek_state.allowed_payment_mints.contains(&user_token_account.mint)
+            @ SleekError::UnsupportedPaymentMint,
+        constraint = user_token_account.owner == user.key() @ SleekError::InvalidTokenAccountOwner
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = authority_token_account.mint == user_token_account.mint
+            @ SleekError::UnsupportedPaymentMint,
+        constraint = authority_token_account.owner == sleek_state.authority
+            @ SleekError::Unauthorized
+    )]
+    pub authority_token_account: Account<'info, TokenAccount>,
+    
+    /// Only required while a fee split is configured
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == sleek_state.fee_recipient
+            @ SleekError::InvalidFeeRecipient,
+        constraint = fee_token_account.mint == user_token_account.mint
+            @ SleekError::UnsupportedPaymentMint
+    )]
+    pub fee_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = user_bonk_account.owner == user.key() @ SleekError::InvalidTokenAccountOwner
+    )]
+    pub user_bonk_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = bonk_mint.key() == sleek_state.bonk_mint @ SleekError::InvalidMint,
+        // Never mint cashback in the token being paid with
+        constraint = bonk_mint.key() != user_token_account.mint @ SleekError::InvalidMint,
+        constraint = bonk_mint.key() != authority_token_account.mint @ SleekError::InvalidMint
+    )]
+    pub bonk_mint: Account<'info, Mint>,
+    
+    /// Only required for subscriptions bought through a plan
+    #[account(
+        mut,
+        seeds = [b"plan", plan.plan_id.to_le_bytes().as_ref()],
+        bump = plan.bump
+    )]
+    pub plan: Option<Account<'info, Plan>>,
+    
+    #[account(constraint = authority.key() == sleek_state.authority @ SleekError::Unauthorized)]
+    pub authority: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemCashback<'info> {
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"profile", user.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + CashbackRedemption::INIT_SPACE,
+        seeds = [b"redemption", user.key().as_ref(), &user_profile.redemption_count.to_le_bytes()],
+        bump
+    )]
+    pub redemption: Account<'info, CashbackRedemption>,
+    
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = user_bonk_account.owner == user.key() @ SleekError::InvalidTokenAccountOwner
+    )]
+    pub user_bonk_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = bonk_mint.key() == sleek_state.bonk_mint @ SleekError::InvalidMint
+    )]
+    pub bonk_mint: Account<'info, Mint>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GiftCashback<'info> {
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"profile", user.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + CashbackGift::INIT_SPACE,
+        seeds = [b"gift", user.key().as_ref(), &user_profile.gift_count.to_le_bytes()],
+        bump
+    )]
+    pub gift: Account<'info, CashbackGift>,
+    
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = sender_bonk_account.owner == user.key() @ SleekError::InvalidTokenAccountOwner,
+        constraint = sender_bonk_account.mint == sleek_state.bonk_mint @ SleekError::InvalidMint
+    )]
+    pub sender_bonk_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = recipient_bonk_account.mint == sleek_state.bonk_mint @ SleekError::InvalidMint,
+        constraint = recipient_bonk_account.owner != user.key() @ SleekError::InvalidGiftRecipient
+    )]
+    pub recipient_bonk_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AirdropCashback<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + CashbackAirdrop::INIT_SPACE,
+        seeds = [
+            b"airdrop",
+            recipient_bonk_account.owner.as_ref(),
+            &sleek_state.airdrop_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub airdrop: Account<'info, CashbackAirdrop>,
+    
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = authority @ SleekError::Unauthorized
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(
+        mut,
+        constraint = recipient_bonk_account.mint == sleek_state.bonk_mint @ SleekError::InvalidMint
+    )]
+    pub recipient_bonk_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = bonk_mint.key() == sleek_state.bonk_mint @ SleekError::InvalidMint
+    )]
+    pub bonk_mint: Account<'info, Mint>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSubscription<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription", user.key().as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    pub user: Signer<'info>,
+    
+    /// Only required for subscriptions bought through a plan
+    #[account(
+        mut,
+        seeds = [b"plan", plan.plan_id.to_le_bytes().as_ref()],
+        bump = plan.bump
+    )]
+    pub plan: Option<Account<'info, Plan>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSubscriptionWithRefund<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription", user.key().as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = authority @ SleekError::Unauthorized
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key() @ SleekError::InvalidTokenAccountOwner,
+        constraint = user_token_account.mint == payment.payment_mint
+            @ SleekError::UnsupportedPaymentMint
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = authority_token_account.mint == payment.payment_mint
+            @ SleekError::UnsupportedPaymentMint,
+        constraint = authority_token_account.owner == sleek_state.authority
+            @ SleekError::Unauthorized
+    )]
+    pub authority_token_account: Account<'info, TokenAccount>,
+    
+    /// The payment that opened the current period; a refund marks it refunded
+    /// so it cannot be refunded in full again
+    #[account(
+        mut,
+        constraint = payment.key() == subscription.last_payment @ SleekError::PaymentMismatch
+    )]
+    pub payment: Account<'info, Payment>,
+    
+    #[account(
+        mut,
+        constraint = user_bonk_account.owner == user.key() @ SleekError::InvalidTokenAccountOwner
+    )]
+    pub user_bonk_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = bonk_mint.key() == sleek_state.bonk_mint @ SleekError::InvalidMint
+    )]
+    pub bonk_mint: Account<'info, Mint>,
+    
+    pub authority: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    /// Only required for subscriptions bought through a plan
+    #[account(
+        mut,
+        seeds = [b"plan", plan.plan_id.to_le_bytes().as_ref()],
+        bump = plan.bump
+    )]
+    pub plan: Option<Account<'info, Plan>>,
+}
+
+#[derive(Accounts)]
+pub struct PauseSubscription<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription", user.key().as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReactivateSubscription<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription", user.key().as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    pub user: Signer<'info>,
+    
+    /// Only required for subscriptions bought through a plan
+    #[account(
+        mut,
+        seeds = [b"plan", plan.plan_id.to_le_bytes().as_ref()],
+        bump = plan.bump
+    )]
+    pub plan: Option<Account<'info, Plan>>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_plan_id: u64)]
+pub struct ChangePlan<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription", user.key().as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = authority @ SleekError::Unauthorized
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    /// Only required when the subscription is currently on a plan
+    #[account(
+        mut,
+        seeds = [b"plan", current_plan.plan_id.to_le_bytes().as_ref()],
+        bump = current_plan.bump
+    )]
+    pub current_plan: Option<Account<'info, Plan>>,
+    
+    #[account(
+        mut,
+        seeds = [b"plan", new_plan_id.to_le_bytes().as_ref()],
+        bump = new_plan.bump
+    )]
+    pub new_plan: Account<'info, Plan>,
+    
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = sleek_state.allowed_payment_mints.contains(&user_token_account.mint)
+            @ SleekError::UnsupportedPaymentMint,
+        constraint = user_token_account.owner == user.key() @ SleekError::InvalidTokenAccountOwner
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = authority_token_account.mint == user_token_account.mint
+            @ SleekError::UnsupportedPaymentMint,
+        constraint = authority_token_account.owner == sleek_state.authority
+            @ SleekError::Unauthorized
+    )]
+    pub authority_token_account: Account<'info, TokenAccount>,
+    
+    /// Only required while a fee split is configured and the change is charged
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == sleek_state.fee_recipient
+            @ SleekError::InvalidFeeRecipient,
+        constraint = fee_token_account.mint == user_token_account.mint
+            @ SleekError::UnsupportedPaymentMint
+    )]
+    pub fee_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub authority: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetSavingsSplit<'info> {
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"profile", user.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = bonk_mint @ SleekError::InvalidMint
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    pub bonk_mint: Account<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"savings", user.key().as_ref()],
+        bump,
+        token::mint = bonk_mint,
+        token::authority = sleek_state
+    )]
+    pub savings_bonk_account: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSavings<'info> {
+    #[account(
+        seeds = [b"profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(
+        mut,
+        seeds = [b"savings", user.key().as_ref()],
+        bump
+    )]
+    pub savings_bonk_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = user_bonk_account.owner == user.key() @ SleekError::InvalidTokenAccountOwner
+    )]
+    pub user_bonk_account: Account<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetSubscriptionTag<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription", user.key().as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAutoRenew<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription", user.key().as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_owner: Pubkey)]
+pub struct TransferSubscription<'info> {
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"subscription", user.key().as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        init,
         payer = user,
-        space = 8 + CashbackRedemption::INIT_SPACE,
-        seeds = [b"redemption", user.key().as_ref(), &Clock::get()?.unix_timestamp.to_le_bytes()],
+        space = 8 + Subscription::INIT_SPACE,
+        seeds = [b"subscription", new_owner.as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump
+    )]
+    pub new_subscription: Account<'info, Subscription>,
+    
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"profile", new_owner.as_ref()],
+        bump
+    )]
+    pub new_owner_profile: Account<'info, UserProfile>,
+    
+    /// The page of the current owner's index that lists the subscription
+    #[account(
+        mut,
+        seeds = [
+            b"subscription_index",
+            user.key().as_ref(),
+            &user_subscription_index.page.to_le_bytes()
+        ],
+        bump = user_subscription_index.bump
+    )]
+    pub user_subscription_index: Account<'info, SubscriptionIndex>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + SubscriptionIndex::INIT_SPACE,
+        seeds = [
+            b"subscription_index",
+            new_owner.as_ref(),
+            &new_owner_profile.index_page().to_le_bytes()
+        ],
+        bump
+    )]
+    pub new_owner_subscription_index: Account<'info, SubscriptionIndex>,
+    
+    /// CHECK: the subscription's recurring authorization, if one was ever
+    /// created; pinned by its seeds and closed when it holds data
+    #[account(
+        mut,
+        seeds = [b"recurring", subscription.key().as_ref()],
+        bump
+    )]
+    pub recurring: UncheckedAccount<'info>,
+    
+    /// Only required once the subscription's NFT has been minted
+    #[account(
+        mut,
+        constraint = user_nft_account.owner == user.key() @ SleekError::InvalidTokenAccountOwner
+    )]
+    pub user_nft_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Only required once the subscription's NFT has been minted
+    #[account(
+        mut,
+        constraint = new_owner_nft_account.owner == new_owner @ SleekError::InvalidTokenAccountOwner
+    )]
+    pub new_owner_nft_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = user.key() != new_owner @ SleekError::InvalidTransferRecipient
+    )]
+    pub user: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireSubscription<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription", subscription.user.as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", subscription.user.as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    /// Only required for subscriptions bought through a plan
+    #[account(
+        mut,
+        seeds = [b"plan", plan.plan_id.to_le_bytes().as_ref()],
+        bump = plan.bump
+    )]
+    pub plan: Option<Account<'info, Plan>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSubscription<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription", user.key().as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump = subscription.bump,
+        close = user
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    /// Only required for subscriptions bought through a plan
+    #[account(
+        mut,
+        seeds = [b"plan", plan.plan_id.to_le_bytes().as_ref()],
+        bump = plan.bump
+    )]
+    pub plan: Option<Account<'info, Plan>>,
+    
+    /// The page of the owner's index that lists the subscription
+    #[account(
+        mut,
+        seeds = [
+            b"subscription_index",
+            user.key().as_ref(),
+            &subscription_index.page.to_le_bytes()
+        ],
+        bump = subscription_index.bump
+    )]
+    pub subscription_index: Account<'info, SubscriptionIndex>,
+    
+    /// CHECK: the subscription's recurring authorization, if one was ever
+    /// created; pinned by its seeds and closed when it holds data
+    #[account(
+        mut,
+        seeds = [b"recurring", subscription.key().as_ref()],
+        bump
+    )]
+    pub recurring: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireAndReward<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription", subscription.user.as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", subscription.user.as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    /// Only required for subscriptions bought through a plan
+    #[account(
+        mut,
+        seeds = [b"plan", plan.plan_id.to_le_bytes().as_ref()],
+        bump = plan.bump
+    )]
+    pub plan: Option<Account<'info, Plan>>,
+    
+    #[account(
+        mut,
+        seeds = [b"keeper_vault"],
+        bump
+    )]
+    pub keeper_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = keeper_bonk_account.mint == sleek_state.bonk_mint @ SleekError::InvalidMint,
+        constraint = keeper_bonk_account.owner == keeper.key() @ SleekError::Unauthorized
+    )]
+    pub keeper_bonk_account: Account<'info, TokenAccount>,
+    
+    pub keeper: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct FundKeeperRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = authority @ SleekError::Unauthorized,
+        has_one = bonk_mint @ SleekError::InvalidMint
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(mut)]
+    pub bonk_mint: Account<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        seeds = [b"keeper_vault"],
+        bump,
+        token::mint = bonk_mint,
+        token::authority = sleek_state
+    )]
+    pub keeper_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AuthorizeRecurring<'info> {
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + RecurringAuthorization::INIT_SPACE,
+        seeds = [b"recurring", subscription.key().as_ref()],
         bump
     )]
-    pub redemption: Account<'info, CashbackRedemption>,
+    pub recurring: Account<'info, RecurringAuthorization>,
+    
+    #[account(
+        mut,
+        seeds = [b"subscription", user.key().as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = sleek_state.allowed_payment_mints.contains(&user_token_account.mint)
+            @ SleekError::UnsupportedPaymentMint,
+        constraint = user_token_account.owner == user.key() @ SleekError::InvalidTokenAccountOwner
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ChargeRecurring<'info> {
+    #[account(
+        mut,
+        seeds = [b"recurring", subscription.key().as_ref()],
+        bump = recurring.bump,
+        has_one = payment_account @ SleekError::Unauthorized
+    )]
+    pub recurring: Account<'info, RecurringAuthorization>,
+    
+    #[account(
+        mut,
+        seeds = [b"subscription", subscription.user.as_ref(), &subscription.subscription_id.to_le_bytes()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", subscription.user.as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(mut)]
+    pub payment_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = authority_token_account.mint == payment_account.mint
+            @ SleekError::UnsupportedPaymentMint,
+        constraint = authority_token_account.owner == sleek_state.authority
+            @ SleekError::Unauthorized
+    )]
+    pub authority_token_account: Account<'info, TokenAccount>,
+    
+    /// Only required while a fee split is configured
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == sleek_state.fee_recipient
+            @ SleekError::InvalidFeeRecipient,
+        constraint = fee_token_account.mint == payment_account.mint
+            @ SleekError::UnsupportedPaymentMint
+    )]
+    pub fee_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Only required for subscriptions bought through a plan
+    #[account(
+        mut,
+        seeds = [b"plan", plan.plan_id.to_le_bytes().as_ref()],
+        bump = plan.bump
+    )]
+    pub plan: Option<Account<'info, Plan>>,
+    
+    pub keeper: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePayment<'info> {
+    #[account(
+        mut,
+        has_one = user,
+        close = user
+    )]
+    pub payment: Account<'info, Payment>,
     
-    #[account(mut)]
-    pub user: Signer<'info>,
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
     
+    /// CHECK: receives the rent; validated against `payment.user`
     #[account(mut)]
-    pub user_bonk_account: Account<'info, TokenAccount>,
+    pub user: UncheckedAccount<'info>,
+    
+    /// Either the paying user or the program authority
+    pub closer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MarkFulfilled<'info> {
+    #[account(
+        mut,
+        seeds = [b"intent", fulfillment_intent.payment.as_ref()],
+        bump = fulfillment_intent.bump
+    )]
+    pub fulfillment_intent: Account<'info, FulfillmentIntent>,
+    
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = authority @ SleekError::Unauthorized
+    )]
+    pub sleek_state: Account<'info, SleekState>,
     
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RefundPayment<'info> {
     #[account(mut)]
-    pub bonk_mint: Account<'info, Mint>,
+    pub payment: Account<'info, Payment>,
+    
+    #[account(
+        mut,
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump,
+        has_one = authority @ SleekError::Unauthorized
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", payment.user.as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"subscription", payment.user.as_ref(), &payment.subscription_id.to_le_bytes()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    /// Only required for subscriptions bought through a plan
+    #[account(
+        mut,
+        seeds = [b"plan", plan.plan_id.to_le_bytes().as_ref()],
+        bump = plan.bump
+    )]
+    pub plan: Option<Account<'info, Plan>>,
+    
+    #[account(
+        mut,
+        constraint = user_token_account.owner == payment.user @ SleekError::Unauthorized,
+        constraint = user_token_account.mint == payment.payment_mint
+            @ SleekError::UnsupportedPaymentMint
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = authority_token_account.mint == payment.payment_mint
+            @ SleekError::UnsupportedPaymentMint,
+        constraint = authority_token_account.owner == sleek_state.authority
+            @ SleekError::Unauthorized
+    )]
+    pub authority_token_account: Account<'info, TokenAccount>,
+    
+    pub authority: Signer<'info>,
     
     pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CancelSubscription<'info> {
+pub struct MintSubscriptionNft<'info> {
     #[account(
         mut,
         seeds = [b"subscription", user.key().as_ref(), &subscription.subscription_id.to_le_bytes()],
@@ -252,23 +4956,299 @@ pub struct CancelSubscription<'info> {
     )]
     pub subscription: Account<'info, Subscription>,
     
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"subscription_nft", subscription.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = sleek_state
+    )]
+    pub nft_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = nft_mint,
+        associated_token::authority = user
+    )]
+    pub user_nft_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: created and validated by the token metadata program
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), nft_mint.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub metadata: UncheckedAccount<'info>,
+    
+    #[account(mut)]
     pub user: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
 pub struct GetCashbackBalance<'info> {
-    #[account(mut)]
+    /// Only read, so balance checks never write-lock the account
     pub user_bonk_account: Account<'info, TokenAccount>,
 }
 
+#[derive(Accounts)]
+pub struct GetSubscriptionStatus<'info> {
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+}
+
+#[derive(Accounts)]
+pub struct CheckEntitlement<'info> {
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+    
+    /// Only required for subscriptions bought through a plan
+    #[account(
+        seeds = [b"plan", plan.plan_id.to_le_bytes().as_ref()],
+        bump = plan.bump
+    )]
+    pub plan: Option<Account<'info, Plan>>,
+}
+
+#[derive(Accounts)]
+pub struct GetLoyaltyTier<'info> {
+    #[account(
+        seeds = [b"profile", user_profile.user.as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+}
+
+#[derive(Accounts)]
+pub struct GetTreasuryStats<'info> {
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+}
+
+#[derive(Accounts)]
+pub struct GetStats<'info> {
+    #[account(
+        seeds = [b"sleek_state"],
+        bump = sleek_state.bump
+    )]
+    pub sleek_state: Account<'info, SleekState>,
+}
+
+#[derive(Accounts)]
+pub struct GetPlanStats<'info> {
+    #[account(
+        seeds = [b"plan", plan.plan_id.to_le_bytes().as_ref()],
+        bump = plan.bump
+    )]
+    pub plan: Account<'info, Plan>,
+}
+
+#[derive(Accounts)]
+pub struct GetPaymentStatus<'info> {
+    pub payment: Account<'info, Payment>,
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct SleekState {
     pub authority: Pubkey,
+    pub bonk_mint: Pubkey,
     pub bump: u8,
     pub total_subscriptions: u64,
     pub total_payments: u64,
     pub total_cashback_minted: u64,
+    pub cashback_bps: u16,
+    pub paused: bool,
+    pub pending_authority: Option<Pubkey>,
+    pub redemption_count: u64,
+    pub tier_configs: [TierConfig; TIER_COUNT],
+    pub payment_retention_secs: i64,
+    #[max_len(MAX_PAYMENT_MINTS)]
+    pub allowed_payment_mints: Vec<Pubkey>,
+    pub max_cashback_per_user: u64,
+    pub revert_on_cashback_cap: bool,
+    pub referral_bonus_bps: u16,
+    pub grace_period_secs: i64,
+    pub loyalty_thresholds: [u64; LOYALTY_TIER_COUNT],
+    pub loyalty_multipliers_bps: [u16; LOYALTY_TIER_COUNT],
+    pub min_duration_secs: i64,
+    pub max_duration_secs: i64,
+    pub total_collected: u64,
+    pub total_refunded: u64,
+    pub min_cashback: u64,
+    pub airdrop_count: u64,
+    pub total_airdropped: u64,
+    pub max_airdrop_total: u64,
+    pub fee_recipient: Pubkey,
+    pub fee_bps: u16,
+    pub redemption_cooldown_secs: i64,
+    pub max_subscriptions_per_user: u32,
+    pub promo_start: i64,
+    pub promo_end: i64,
+    pub promo_cashback_bps: u16,
+    pub extend_from_now_if_expired: bool,
+    pub redemptions_paused: bool,
+    pub version: u8,
+    pub payment_decimals: u8,
+    pub cashback_decimals: u8,
+    pub min_redemption_amount: u64,
+    pub allowlist_enabled: bool,
+    pub total_active_subscriptions: u64,
+    pub keeper_reward: u64,
+    pub keeper_reward_budget: u64,
+    pub annual_discount_bps: u16,
+    pub refund_window_secs: i64,
+    pub trial_secs: i64,
+    pub bonk_per_lamport: u64,
+    pub max_total_cashback: u64,
+    pub revert_on_cashback_budget: bool,
+    #[max_len(MAX_ADMINS)]
+    pub admins: Vec<Pubkey>,
+    pub savings_lock_secs: i64,
+    /// Payments a user may make per `payment_window_secs`; zero disables the limit
+    pub max_payments_per_window: u32,
+    pub payment_window_secs: i64,
+    /// Smallest payment accepted, in the payment token's base units; zero disables it
+    pub min_payment_amount: u64,
+    /// Consecutive failed recurring charges that expire a subscription; zero never expires it
+    pub max_renewal_failures: u8,
+    /// Pyth SOL/USD feed SOL payments are priced against; zero disables the check
+    pub price_feed_id: [u8; 32],
+    pub price_tolerance_bps: u16,
+    pub max_price_age_secs: i64,
+    /// How long after cancelling a resubscription earns the winback bonus
+    pub winback_window_secs: i64,
+    /// One-time cashback bonus for churned users; zero disables it
+    pub winback_bonus_bps: u16,
+    /// Feature bits each tier grants subscriptions bought without a plan
+    pub tier_features: [u32; TIER_COUNT],
+    /// Id `process_subscription_payment_auto_id` assigns next
+    pub next_subscription_id: u64,
+    /// Wait after a failed recurring charge before the next attempt
+    pub renewal_retry_secs: i64,
+}
+
+impl SleekState {
+    /// Whether `key` may sign admin-only instructions
+    pub fn is_admin(&self, key: &Pubkey) -> bool {
+        *key == self.authority || self.admins.contains(key)
+    }
+
+    /// Start of a renewed period. Within the grace period it stacks on the
+    /// current expiration; after a lapse it starts at `now` when
+    /// `extend_from_now_if_expired` is set, otherwise it stacks anyway
+    pub fn renewal_start(&self, expiration_date: i64, now: i64) -> Result<i64> {
+        let lapsed = now > add_secs(expiration_date, self.grace_period_secs)?;
+        if !lapsed || !self.extend_from_now_if_expired {
+            Ok(expiration_date)
+        } else {
+            Ok(now)
+        }
+    }
+
+    /// Partner fee and treasury share of `amount`; rounding favours the treasury
+    pub fn split_fee(&self, amount: u64) -> Result<(u64, u64)> {
+        let fee_amount = checked_mul_div(amount, self.fee_bps as u64, BPS_DENOMINATOR)?;
+        Ok((fee_amount, checked_sub(amount, fee_amount)?))
+    }
+
+    /// Convert a payment-token amount into cashback-mint units
+    pub fn to_cashback_units(&self, amount: u64) -> Result<u64> {
+        if self.cashback_decimals >= self.payment_decimals {
+            checked_mul(amount, checked_pow10(self.cashback_decimals - self.payment_decimals)?)
+        } else {
+            Ok(amount / checked_pow10(self.payment_decimals - self.cashback_decimals)?)
+        }
+    }
+
+    /// Whether the promo window covers `now`
+    pub fn promo_active(&self, now: i64) -> bool {
+        self.promo_start <= now && now < self.promo_end
+    }
+
+    /// Promo rate inside the promo window, otherwise the configured base rate
+    pub fn base_cashback_bps(&self, now: i64) -> u16 {
+        if self.promo_active(now) {
+            self.promo_cashback_bps
+        } else {
+            self.cashback_bps
+        }
+    }
+
+    /// Base cashback rate at `now` plus the tier's bonus, capped at `MAX_CASHBACK_BPS`
+    pub fn tier_cashback_bps(&self, tier: Tier, now: i64) -> u16 {
+        self.base_cashback_bps(now)
+            .saturating_add(self.tier_configs[tier.index()].bonus_cashback_bps)
+            .min(MAX_CASHBACK_BPS)
+    }
+
+    /// Loyalty tier index reached after `total_payments` payments
+    pub fn loyalty_tier(&self, total_payments: u64) -> u8 {
+        self.loyalty_thresholds
+            .iter()
+            .rposition(|threshold| total_payments >= *threshold)
+            .unwrap_or(0) as u8
+    }
+
+    /// Tier rate scaled by the user's loyalty multiplier, capped at `MAX_CASHBACK_BPS`
+    pub fn effective_cashback_bps(&self, tier: Tier, total_payments: u64, now: i64) -> u16 {
+        self.loyalty_scaled_bps(self.tier_cashback_bps(tier, now), total_payments)
+    }
+
+    /// Rate a payment earns: its plan's own rate if it has one, otherwise the
+    /// tier's, scaled by the user's loyalty multiplier either way
+    pub fn payment_cashback_bps(
+        &self,
+        plan_cashback_bps: Option<u16>,
+        tier: Tier,
+        total_payments: u64,
+        now: i64,
+    ) -> u16 {
+        match plan_cashback_bps {
+            Some(plan_cashback_bps) => self.loyalty_scaled_bps(plan_cashback_bps, total_payments),
+            None => self.effective_cashback_bps(tier, total_payments, now),
+        }
+    }
+
+    /// `base_bps` scaled by the user's loyalty multiplier, capped at `MAX_CASHBACK_BPS`
+    pub fn loyalty_scaled_bps(&self, base_bps: u16, total_payments: u64) -> u16 {
+        let multiplier = self.loyalty_multipliers_bps[self.loyalty_tier(total_payments) as usize];
+        let bps = base_bps as u64 * multiplier as u64 / BPS_DENOMINATOR;
+        bps.min(MAX_CASHBACK_BPS as u64) as u16
+    }
 }
 
 #[account]
@@ -278,45 +5258,536 @@ pub struct Payment {
     pub subscription_id: u64,
     pub amount: u64,
     pub sol_amount: u64,
+    pub payment_mint: Pubkey,
     pub status: PaymentStatus,
     pub timestamp: i64,
     pub bump: u8,
+    pub cashback_minted: u64,
+    pub fee_amount: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Subscription {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub amount: u64,
+    pub tier: Tier,
+    pub status: SubscriptionStatus,
+    pub activation_date: i64,
+    pub expiration_date: i64,
+    pub cancellation_date: Option<i64>,
+    pub nft_mint: Option<Pubkey>,
+    /// Undiscounted price of one full cycle in the payment mint
+    pub sol_price: u64,
+    /// Paid toward the period since `activation_date`, across payments, renewals and installments
+    pub amount_paid: u64,
+    /// Share of `amount_paid` the treasury kept after partner fees
+    pub treasury_paid: u64,
+    /// Whether the keeper may charge and extend this subscription
+    pub auto_renew: bool,
+    /// Plan this subscription was bought through, if any
+    pub plan_id: Option<u64>,
+    /// Off-chain JSON describing the plan, artwork and renewal terms
+    #[max_len(MAX_METADATA_URI_LEN)]
+    pub metadata_uri: String,
+    /// Why the user cancelled, if the subscription is cancelled
+    pub cancellation_reason: Option<CancellationReason>,
+    /// Cadence the subscription was bought at
+    pub billing_period: BillingPeriod,
+    /// When the current hold started, if the subscription is paused
+    pub paused_at: Option<i64>,
+    /// Recurring charges that failed since the last successful one
+    pub renewal_failures: u32,
+    /// Owner's cost-center tag or PO number for their accounting
+    #[max_len(MAX_TAG_LEN)]
+    pub tag: String,
+    /// Length of the billing period `sol_price` buys, fixed at purchase
+    pub period_secs: i64,
+    /// Payment that opened the current period; unset for trials
+    pub last_payment: Pubkey,
+    /// Cashback minted toward the period since `activation_date`
+    pub period_cashback: u64,
+    pub bump: u8,
+}
+
+impl Subscription {
+    /// Status as of `now`; a subscription lapsed past the grace period reads as
+    /// expired even if not yet cranked. Paused subscriptions never lapse
+    pub fn effective_status(&self, now: i64, grace_period_secs: i64) -> SubscriptionStatus {
+        if self.status != SubscriptionStatus::Paused
+            && now > self.expiration_date.saturating_add(grace_period_secs)
+        {
+            SubscriptionStatus::Expired
+        } else {
+            self.status
+        }
+    }
+
+    /// Whether the subscription grants access as of `now`; see `effective_status`
+    pub fn is_valid(&self, now: i64, grace_period_secs: i64) -> bool {
+        self.effective_status(now, grace_period_secs) == SubscriptionStatus::Active
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UserProfile {
+    pub user: Pubkey,
+    pub total_spent: u64,
+    pub total_payments: u64,
+    pub total_cashback_earned: u64,
+    pub active_subscriptions: u32,
+    pub first_seen: i64,
+    pub bump: u8,
+    pub indexed_subscriptions: u64,
+    pub last_redemption_at: i64,
+    pub frozen: bool,
+    pub redemption_count: u64,
+    pub allowlisted: bool,
+    pub trial_used: bool,
+    /// Share of payment cashback minted into the savings vault
+    pub savings_split_bps: u16,
+    /// Earliest time the savings vault may be withdrawn
+    pub savings_unlock_at: i64,
+    /// Payments made since `payment_window_start`
+    pub payments_in_window: u32,
+    pub payment_window_start: i64,
+    /// Cashback gifts sent, seeding the next gift PDA
+    pub gift_count: u64,
+    /// When the user last cancelled a subscription; zero if never
+    pub last_cancelled_at: i64,
+    pub winback_claimed: bool,
+}
+
+impl UserProfile {
+    /// `SubscriptionIndex` page the next new subscription id is recorded on
+    pub fn index_page(&self) -> u32 {
+        (self.indexed_subscriptions / SUBSCRIPTION_INDEX_PAGE_SIZE as u64) as u32
+    }
+
+    /// Fail if one more active subscription would exceed `max_subscriptions_per_user`
+    pub fn require_subscription_slot(&self, sleek_state: &SleekState) -> Result<()> {
+        require!(
+            sleek_state.max_subscriptions_per_user == 0
+                || self.active_subscriptions < sleek_state.max_subscriptions_per_user,
+            SleekError::TooManySubscriptions
+        );
+        Ok(())
+    }
+
+    /// `cashback_bps` plus the one-time winback bonus when the user resubscribes
+    /// within `winback_window_secs` of their last cancellation, capped at
+    /// `MAX_CASHBACK_BPS`
+    pub fn apply_winback(
+        &mut self,
+        cashback_bps: u16,
+        subscription_id: u64,
+        now: i64,
+        sleek_state: &SleekState,
+    ) -> Result<u16> {
+        if sleek_state.winback_bonus_bps == 0 || self.winback_claimed || self.last_cancelled_at == 0
+        {
+            return Ok(cashback_bps);
+        }
+        if now > add_secs(self.last_cancelled_at, sleek_state.winback_window_secs)? {
+            return Ok(cashback_bps);
+        }
+
+        self.winback_claimed = true;
+        emit!(WinbackApplied {
+            user: self.user,
+            subscription_id,
+            bonus_bps: sleek_state.winback_bonus_bps,
+        });
+        Ok(cashback_bps
+            .saturating_add(sleek_state.winback_bonus_bps)
+            .min(MAX_CASHBACK_BPS))
+    }
+
+    /// Count a payment made at `now` against the per-window rate limit, starting
+    /// a fresh window once the current one has passed
+    pub fn record_payment(&mut self, now: i64, sleek_state: &SleekState) -> Result<()> {
+        if sleek_state.max_payments_per_window == 0 {
+            return Ok(());
+        }
+
+        if now >= add_secs(self.payment_window_start, sleek_state.payment_window_secs)? {
+            self.payment_window_start = now;
+            self.payments_in_window = 0;
+        }
+        require!(
+            self.payments_in_window < sleek_state.max_payments_per_window,
+            SleekError::PaymentRateLimited
+        );
+        self.payments_in_window = checked_add(self.payments_in_window, 1)?;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SubscriptionIndex {
+    pub user: Pubkey,
+    pub page: u32,
+    #[max_len(SUBSCRIPTION_INDEX_PAGE_SIZE)]
+    pub subscription_ids: Vec<u64>,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Coupon {
+    pub code_hash: [u8; 32],
+    pub discount_bps: u16,
+    pub max_redemptions: u32,
+    pub times_redeemed: u32,
+    pub expiry: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Plan {
+    pub plan_id: u64,
+    #[max_len(MAX_PLAN_NAME_LEN)]
+    pub name: String,
+    pub price: u64,
+    pub duration_secs: i64,
+    pub cashback_bps: u16,
+    pub active: bool,
+    pub bump: u8,
+    pub active_subscribers: u64,
+    pub lifetime_subscribers: u64,
+    /// Feature bits the plan's subscriptions are entitled to
+    pub features: u32,
+    /// Tier recorded on subscriptions bought through the plan
+    pub tier: Tier,
+    /// Mint `price` is denominated in; native SOL plans use the native mint
+    pub payment_mint: Pubkey,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct CashbackRedemption {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct CashbackGift {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct CashbackAirdrop {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+    pub bump: u8,
 }
 
+/// Marker the backend polls to provision a service for a payment, keyed by the
+/// client-supplied `service_ref`
 #[account]
 #[derive(InitSpace)]
-pub struct Subscription {
+pub struct FulfillmentIntent {
     pub user: Pubkey,
+    pub payment: Pubkey,
     pub subscription_id: u64,
-    pub amount: u64,
-    pub status: SubscriptionStatus,
-    pub activation_date: i64,
-    pub expiration_date: i64,
-    pub cancellation_date: Option<i64>,
+    pub service_ref: [u8; 32],
+    pub fulfilled: bool,
+    pub created_at: i64,
+    pub fulfilled_at: i64,
     pub bump: u8,
 }
 
 #[account]
 #[derive(InitSpace)]
-pub struct CashbackRedemption {
+pub struct RecurringAuthorization {
     pub user: Pubkey,
-    pub amount: u64,
-    pub timestamp: i64,
+    pub subscription_id: u64,
+    pub payment_account: Pubkey,
+    pub max_per_cycle: u64,
+    pub cycles_remaining: u32,
+    pub next_charge_at: i64,
     pub bump: u8,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+/// New variants must be appended: Borsh stores the variant index, so
+/// reordering would change how existing `Payment` accounts decode.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
 pub enum PaymentStatus {
     Pending,
     Completed,
     Failed,
+    Refunded,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
 pub enum SubscriptionStatus {
     Active,
     Expired,
     Cancelled,
+    Paused,
+}
+
+/// How much time a payment buys: one tier cycle, or a discounted year
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum BillingPeriod {
+    Monthly,
+    Annual,
+}
+
+/// Churn category a user gives when cancelling
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum CancellationReason {
+    TooExpensive,
+    NotUsing,
+    SwitchingProvider,
+    Other,
+}
+
+/// Return value of `get_subscription_status`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SubscriptionStatusInfo {
+    pub status: SubscriptionStatus,
+    pub expiration_date: i64,
+    pub seconds_remaining: i64,
+}
+
+/// Return value of `check_entitlement`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Entitlement {
+    pub valid: bool,
+    pub tier: Tier,
+    pub seconds_remaining: i64,
+    pub features_bitmask: u32,
+}
+
+/// Return value of `get_treasury_stats`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TreasuryStats {
+    pub total_collected: u64,
+    pub total_refunded: u64,
+    pub net_collected: u64,
+}
+
+/// Return value of `get_stats`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SleekStats {
+    pub total_subscriptions: u64,
+    pub total_active_subscriptions: u64,
+    pub total_payments: u64,
+    pub total_cashback_minted: u64,
+    pub total_collected: u64,
+    pub total_refunded: u64,
+    pub cashback_bps: u16,
+}
+
+/// Return value of `get_plan_stats`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PlanStats {
+    pub active_subscribers: u64,
+    pub lifetime_subscribers: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CashbackReason {
+    Payment,
+    Referral,
+    Airdrop,
+    Promo,
+    KeeperReward,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RenewalFailureReason {
+    InsufficientFunds,
+    DelegateRevoked,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum Tier {
+    Basic,
+    Premium,
+    Enterprise,
+}
+
+impl Tier {
+    pub fn index(self) -> usize {
+        match self {
+            Tier::Basic => 0,
+            Tier::Premium => 1,
+            Tier::Enterprise => 2,
+        }
+    }
+}
+
+/// Per-tier billing settings stored on `SleekState`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct TierConfig {
+    pub duration_secs: i64,
+    /// Added on top of `SleekState::cashback_bps`
+    pub bonus_cashback_bps: u16,
+}
+
+/// Admin-configurable `SleekState` fields, named after the field they set
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigField {
+    CashbackBps,
+    TierDurationSecs,
+    TierBonusCashbackBps,
+    PaymentRetentionSecs,
+    PaymentMint,
+    MaxCashbackPerUser,
+    RevertOnCashbackCap,
+    MaxTotalCashback,
+    RevertOnCashbackBudget,
+    MinCashback,
+    MinPaymentAmount,
+    MaxRenewalFailures,
+    PriceFeedId,
+    PriceToleranceBps,
+    MaxPriceAgeSecs,
+    MaxAirdropTotal,
+    FeeRecipient,
+    FeeBps,
+    RedemptionCooldownSecs,
+    MinRedemptionAmount,
+    KeeperReward,
+    RefundWindowSecs,
+    SavingsLockSecs,
+    MaxPaymentsPerWindow,
+    PaymentWindowSecs,
+    TrialSecs,
+    BonkPerLamport,
+    MaxSubscriptionsPerUser,
+    PromoStart,
+    PromoEnd,
+    PromoCashbackBps,
+    ExtendFromNowIfExpired,
+    PaymentDecimals,
+    CashbackDecimals,
+    AnnualDiscountBps,
+    ReferralBonusBps,
+    GracePeriodSecs,
+    MinDurationSecs,
+    MaxDurationSecs,
+    LoyaltyThreshold,
+    LoyaltyMultiplierBps,
+    Paused,
+    RedemptionsPaused,
+    AllowlistEnabled,
+    PendingAuthority,
+    WinbackWindowSecs,
+    WinbackBonusBps,
+    TierFeatures,
+    RenewalRetrySecs,
+    CouponDiscountBps,
+    CouponMaxRedemptions,
+    CouponExpiry,
+    PlanPrice,
+    PlanDurationSecs,
+    PlanCashbackBps,
+    PlanFeatures,
+    PlanActive,
+    UserFrozen,
+    Allowlisted,
+}
+
+/// A numeric or boolean config field changed; see `config::set_config`
+#[event]
+pub struct ConfigChanged {
+    pub field: ConfigField,
+    /// Tier or loyalty level of per-level fields, otherwise zero
+    pub index: u8,
+    pub old_value: u64,
+    pub new_value: u64,
+    pub admin: Pubkey,
+}
+
+/// A key-valued config field changed; adding a payment mint logs a default
+/// `old_value` and removing one a default `new_value`
+#[event]
+pub struct ConfigKeyChanged {
+    pub field: ConfigField,
+    pub old_value: Pubkey,
+    pub new_value: Pubkey,
+    pub admin: Pubkey,
+}
+
+/// A numeric or boolean field of a plan, coupon or user profile changed; see
+/// `config::set_account_config`. Creating a plan or coupon logs zero old values
+#[event]
+pub struct AccountConfigChanged {
+    pub field: ConfigField,
+    pub account: Pubkey,
+    pub old_value: u64,
+    pub new_value: u64,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct CashbackRateUpdated {
+    pub old_cashback_bps: u16,
+    pub new_cashback_bps: u16,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub old: Pubkey,
+    pub new: Pubkey,
+}
+
+#[event]
+pub struct CashbackBalanceRead {
+    pub user: Pubkey,
+    pub balance: u64,
+}
+
+#[event]
+pub struct EntitlementRead {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub valid: bool,
+    pub features_bitmask: u32,
+}
+
+#[event]
+pub struct SubscriptionStatusRead {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub status: SubscriptionStatus,
+    pub expiration_date: i64,
+    pub seconds_remaining: i64,
+}
+
+#[event]
+pub struct DaysRemainingRead {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub days_remaining: i64,
+}
+
+#[event]
+pub struct StateMigrated {
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[event]
+pub struct MintAuthorityChanged {
+    pub old: Pubkey,
+    pub new: Pubkey,
 }
 
 #[event]
@@ -325,18 +5796,280 @@ pub struct PaymentProcessed {
     pub subscription_id: u64,
     pub amount: u64,
     pub cashback_amount: u64,
+    pub tier: Tier,
+    pub timestamp: i64,
+    pub payment: Pubkey,
+    pub sol_amount: u64,
+    pub expiration_date: i64,
+    pub cashback_bps: u16,
+    pub promo_applied: bool,
+}
+
+#[event]
+pub struct PaymentSplit {
+    pub user: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub fee_amount: u64,
+    pub treasury_amount: u64,
+}
+
+#[event]
+pub struct ReferralRewarded {
+    pub referrer: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct WinbackApplied {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub bonus_bps: u16,
+}
+
+#[event]
+pub struct SubscriptionRenewed {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub cashback_amount: u64,
+    pub previous_expiration_date: i64,
+    pub new_expiration_date: i64,
+}
+
+#[event]
+pub struct InstallmentPaid {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub sol_amount: u64,
+    pub amount_paid: u64,
+    pub cashback_amount: u64,
+    pub new_expiration_date: i64,
 }
 
 #[event]
 pub struct CashbackRedeemed {
     pub user: Pubkey,
     pub amount: u64,
+    pub remaining_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CashbackApplied {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub cashback_burned: u64,
+    pub discount: u64,
+}
+
+#[event]
+pub struct CashbackMinted {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub reason: CashbackReason,
+}
+
+#[event]
+pub struct UserFrozenStatusChanged {
+    pub user: Pubkey,
+    pub frozen: bool,
+}
+
+#[event]
+pub struct SubscriptionMetadataUpdated {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub metadata_uri: String,
+}
+
+#[event]
+pub struct KeeperRewarded {
+    pub keeper: Pubkey,
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AdminStatusChanged {
+    pub admin: Pubkey,
+    pub is_admin: bool,
+}
+
+#[event]
+pub struct SavingsSplitChanged {
+    pub user: Pubkey,
+    pub savings_split_bps: u16,
+}
+
+#[event]
+pub struct SavingsWithdrawn {
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AllowlistStatusChanged {
+    pub user: Pubkey,
+    pub allowlisted: bool,
+}
+
+#[event]
+pub struct AirdropMinted {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub total_airdropped: u64,
+}
+
+#[event]
+pub struct CashbackGifted {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct SubscriptionCreated {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub tier: Tier,
+    pub activation_date: i64,
+    pub expiration_date: i64,
+    pub billing_period: BillingPeriod,
+    pub tag: String,
+}
+
+#[event]
+pub struct SubscriptionTagUpdated {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub tag: String,
 }
 
 #[event]
 pub struct SubscriptionCancelled {
     pub user: Pubkey,
     pub subscription_id: u64,
+    pub refund_amount: u64,
+    pub refund_issued: bool,
+    pub reason: CancellationReason,
+    pub cashback_clawed_back: u64,
+}
+
+#[event]
+pub struct PlanChanged {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub from: Option<u64>,
+    pub to: u64,
+    pub proration_credit: u64,
+    pub amount_charged: u64,
+    pub amount_refunded: u64,
+}
+
+#[event]
+pub struct SubscriptionPaused {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub paused_at: i64,
+}
+
+#[event]
+pub struct SubscriptionResumed {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub paused_secs: i64,
+    pub expiration_date: i64,
+}
+
+#[event]
+pub struct SubscriptionReactivated {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub expiration_date: i64,
+}
+
+#[event]
+pub struct SubscriptionTransferred {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub subscription_id: u64,
+}
+
+#[event]
+pub struct AutoRenewChanged {
+    pub subscription_id: u64,
+    pub enabled: bool,
+}
+
+#[event]
+pub struct SubscriptionExpired {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub expiration_date: i64,
+}
+
+#[event]
+pub struct SubscriptionClosed {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub status: SubscriptionStatus,
+}
+
+#[event]
+pub struct SubscriptionNftMinted {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub nft_mint: Pubkey,
+}
+
+#[event]
+pub struct PaymentClosed {
+    pub user: Pubkey,
+    pub payment: Pubkey,
+    pub subscription_id: u64,
+}
+
+#[event]
+pub struct Fulfilled {
+    pub user: Pubkey,
+    pub payment: Pubkey,
+    pub subscription_id: u64,
+    pub service_ref: [u8; 32],
+}
+
+#[event]
+pub struct PaymentRefunded {
+    pub user: Pubkey,
+    pub payment: Pubkey,
+    pub subscription_id: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct RecurringAuthorized {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub max_per_cycle: u64,
+    pub cycles: u32,
+    pub next_charge_at: i64,
+}
+
+#[event]
+pub struct RenewalFailed {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub reason: RenewalFailureReason,
+    pub attempt: u32,
+}
+
+#[event]
+pub struct RecurringCharged {
+    pub user: Pubkey,
+    pub subscription_id: u64,
+    pub amount: u64,
+    pub cycles_remaining: u32,
+    pub next_charge_at: i64,
 }
 
 #[error_code]
@@ -347,4 +6080,160 @@ pub enum SleekError {
     SubscriptionNotActive,
     #[msg("Insufficient balance")]
     InsufficientBalance,
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+    #[msg("Invalid cashback mint")]
+    InvalidMint,
+    #[msg("Cashback rate exceeds the allowed maximum")]
+    InvalidCashbackRate,
+    #[msg("Program is paused")]
+    ProgramPaused,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Subscription has not expired yet")]
+    NotYetExpired,
+    #[msg("Invalid tier configuration")]
+    InvalidTierConfig,
+    #[msg("Payment is still within the retention window")]
+    PaymentTooRecent,
+    #[msg("Payment mint is not supported")]
+    UnsupportedPaymentMint,
+    #[msg("Payment mint whitelist is full")]
+    PaymentMintListFull,
+    #[msg("Cashback cap reached for this user")]
+    CashbackCapReached,
+    #[msg("Users cannot refer themselves")]
+    SelfReferral,
+    #[msg("Referrer BONK account is missing or not owned by the referrer")]
+    InvalidReferrer,
+    #[msg("Subscription NFT has already been minted")]
+    NftAlreadyMinted,
+    #[msg("Subscription is not cancelled")]
+    SubscriptionNotCancelled,
+    #[msg("Subscription has expired; renew it instead")]
+    SubscriptionExpired,
+    #[msg("Coupon is invalid, expired, or exhausted")]
+    CouponInvalid,
+    #[msg("Invalid loyalty tier configuration")]
+    InvalidLoyaltyConfig,
+    #[msg("Subscription cannot be paid that far ahead")]
+    MaxDurationExceeded,
+    #[msg("Cannot transfer a subscription to its current owner")]
+    InvalidTransferRecipient,
+    #[msg("Subscription duration is outside the allowed range")]
+    InvalidDuration,
+    #[msg("No recurring cycles remain")]
+    RecurringExhausted,
+    #[msg("Recurring charge is not due yet")]
+    ChargeNotDue,
+    #[msg("Cycle price exceeds the authorized maximum")]
+    RecurringLimitExceeded,
+    #[msg("An active subscription with this id already exists")]
+    SubscriptionAlreadyExists,
+    #[msg("Payment has already been refunded")]
+    AlreadyRefunded,
+    #[msg("Automatic renewal is turned off for this subscription")]
+    AutoRenewDisabled,
+    #[msg("Airdrop would exceed the cumulative airdrop ceiling")]
+    AirdropCeilingExceeded,
+    #[msg("Payment exceeds the maximum SOL amount the user agreed to")]
+    PriceSlippageExceeded,
+    #[msg("Plan is not active")]
+    PlanInactive,
+    #[msg("Invalid plan configuration")]
+    InvalidPlanConfig,
+    #[msg("Token account is not owned by the user")]
+    InvalidTokenAccountOwner,
+    #[msg("Invalid fee split configuration")]
+    InvalidFeeConfig,
+    #[msg("Fee token account does not belong to the fee recipient")]
+    InvalidFeeRecipient,
+    #[msg("Redemption cooldown has not elapsed")]
+    RedemptionCooldown,
+    #[msg("User account is frozen")]
+    AccountFrozen,
+    #[msg("User has reached the maximum number of subscriptions")]
+    TooManySubscriptions,
+    #[msg("Promo window must not end before it starts")]
+    InvalidPromoWindow,
+    #[msg("Subscription cannot move to the requested status")]
+    InvalidStatusTransition,
+    #[msg("Plan account does not match the subscription's plan")]
+    PlanMismatch,
+    #[msg("Metadata URI is too long")]
+    UriTooLong,
+    #[msg("Redemptions are paused")]
+    RedemptionsPaused,
+    #[msg("State account is already at the current version")]
+    StateUpToDate,
+    #[msg("Token decimals differ by too much to scale between")]
+    InvalidDecimals,
+    #[msg("Redemption is below the minimum amount")]
+    RedemptionTooSmall,
+    #[msg("Wallet is not on the allowlist")]
+    NotAllowlisted,
+    #[msg("Keeper reward budget is exhausted")]
+    KeeperBudgetExhausted,
+    #[msg("Subscription is already on this plan")]
+    PlanUnchanged,
+    #[msg("Clock reads earlier than the subscription's activation")]
+    ClockError,
+    #[msg("Discount must be below 100%")]
+    InvalidDiscount,
+    #[msg("Annual billing is only available for tier purchases of the standard length")]
+    InvalidBillingPeriod,
+    #[msg("Free trial has already been used")]
+    TrialAlreadyUsed,
+    #[msg("Free trials are disabled")]
+    TrialsDisabled,
+    #[msg("Paying with cashback is disabled")]
+    CashbackPaymentsDisabled,
+    #[msg("Program-wide cashback budget exhausted")]
+    CashbackBudgetExhausted,
+    #[msg("Admin list is full")]
+    AdminListFull,
+    #[msg("Key is not a secondary admin")]
+    AdminNotFound,
+    #[msg("Payment does not belong to the subscription's current period")]
+    PaymentMismatch,
+    #[msg("Subscription is not paused")]
+    SubscriptionNotPaused,
+    #[msg("Savings split cannot exceed 100%")]
+    InvalidSavingsSplit,
+    #[msg("Savings are still locked")]
+    SavingsLocked,
+    #[msg("Savings BONK account is required to save cashback")]
+    SavingsAccountMissing,
+    #[msg("Subscription is still active")]
+    SubscriptionStillActive,
+    #[msg("Too many payments in the current window")]
+    PaymentRateLimited,
+    #[msg("Fulfillment intent must be passed exactly when a service ref is given")]
+    IntentMismatch,
+    #[msg("Fulfillment intent is already fulfilled")]
+    IntentAlreadyFulfilled,
+    #[msg("Payment is below the minimum amount")]
+    PaymentTooSmall,
+    #[msg("Subscription tag is too long")]
+    TagTooLong,
+    #[msg("Price feed is missing or not the configured SOL/USD feed")]
+    InvalidPriceFeed,
+    #[msg("Price feed has not been updated recently enough")]
+    StalePrice,
+    #[msg("SOL amount is too far from the oracle price")]
+    PriceOutOfRange,
+    #[msg("Cannot gift cashback to yourself")]
+    InvalidGiftRecipient,
+    #[msg("Subscription id is not the next one to be assigned")]
+    SubscriptionIdMismatch,
+    #[msg("A free trial has no price to renew or charge")]
+    TrialNotRenewable,
+    #[msg("A renewal must pay the subscription's full cycle price")]
+    RenewalUnderpaid,
+    #[msg("Subscription ids from AUTO_SUBSCRIPTION_ID_START up are assigned by the program")]
+    SubscriptionIdReserved,
+    #[msg("Subscription is not listed on the given subscription index page")]
+    SubscriptionNotIndexed,
+    #[msg("The NFT's token accounts are required to move a subscription that has one")]
+    NftAccountMissing,
 } 
\ No newline at end of file
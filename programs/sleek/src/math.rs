@@ -0,0 +1,134 @@
+//! Checked arithmetic helpers that fail with `SleekError::ArithmeticOverflow`,
+//! or `SleekError::ClockError` for timestamps that run backwards
+
+use anchor_lang::prelude::*;
+
+use crate::SleekError;
+
+/// Integer types usable with `checked_add` and `checked_sub`
+pub trait CheckedInt: Copy {
+    fn add(self, rhs: Self) -> Option<Self>;
+    fn sub(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked_int {
+    ($($t:ty),*) => {
+        $(impl CheckedInt for $t {
+            fn add(self, rhs: Self) -> Option<Self> {
+                self.checked_add(rhs)
+            }
+
+            fn sub(self, rhs: Self) -> Option<Self> {
+                self.checked_sub(rhs)
+            }
+        })*
+    };
+}
+
+impl_checked_int!(u16, u32, u64, i64);
+
+/// `a + b`
+pub fn checked_add<T: CheckedInt>(a: T, b: T) -> Result<T> {
+    a.add(b).ok_or(error!(SleekError::ArithmeticOverflow))
+}
+
+/// `a - b`
+pub fn checked_sub<T: CheckedInt>(a: T, b: T) -> Result<T> {
+    a.sub(b).ok_or(error!(SleekError::ArithmeticOverflow))
+}
+
+/// `a * b`
+pub fn checked_mul(a: u64, b: u64) -> Result<u64> {
+    a.checked_mul(b).ok_or(error!(SleekError::ArithmeticOverflow))
+}
+
+/// `value * mul / div`, computed with a u128 intermediate
+pub fn checked_mul_div(value: u64, mul: u64, div: u64) -> Result<u64> {
+    let result = (value as u128)
+        .checked_mul(mul as u128)
+        .and_then(|v| v.checked_div(div as u128))
+        .ok_or(SleekError::ArithmeticOverflow)?;
+    u64::try_from(result).map_err(|_| error!(SleekError::ArithmeticOverflow))
+}
+
+/// `10^exp`
+pub fn checked_pow10(exp: u8) -> Result<u64> {
+    10u64
+        .checked_pow(exp as u32)
+        .ok_or(error!(SleekError::ArithmeticOverflow))
+}
+
+/// `timestamp` moved forward by `secs`
+pub fn add_secs(timestamp: i64, secs: i64) -> Result<i64> {
+    checked_add(timestamp, secs)
+}
+
+/// Seconds from `since` to `now`, rejecting a clock that reads earlier than `since`
+pub fn elapsed_secs(since: i64, now: i64) -> Result<i64> {
+    require!(now >= since, SleekError::ClockError);
+    checked_sub(now, since)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_add_at_bounds() {
+        assert_eq!(checked_add(u64::MAX - 1, 1).unwrap(), u64::MAX);
+        assert!(checked_add(u64::MAX, 1).is_err());
+        assert!(checked_add(u32::MAX, 1).is_err());
+        assert!(checked_add(u16::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn checked_sub_at_bounds() {
+        assert_eq!(checked_sub(1u64, 1).unwrap(), 0);
+        assert!(checked_sub(0u64, 1).is_err());
+        assert!(checked_sub(0u32, 1).is_err());
+        assert!(checked_sub(i64::MIN, 1).is_err());
+    }
+
+    #[test]
+    fn checked_mul_at_bounds() {
+        assert_eq!(checked_mul(u64::MAX, 1).unwrap(), u64::MAX);
+        assert!(checked_mul(u64::MAX, 2).is_err());
+    }
+
+    #[test]
+    fn checked_mul_div_uses_wide_intermediate() {
+        assert_eq!(checked_mul_div(u64::MAX, u64::MAX, u64::MAX).unwrap(), u64::MAX);
+        assert_eq!(checked_mul_div(59_940, 1_000, 10_000).unwrap(), 5_994);
+        assert_eq!(checked_mul_div(9, 1_000, 10_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn checked_mul_div_rejects_overflow_and_zero_divisor() {
+        assert!(checked_mul_div(u64::MAX, 2, 1).is_err());
+        assert!(checked_mul_div(1, 1, 0).is_err());
+    }
+
+    #[test]
+    fn checked_pow10_at_bounds() {
+        assert_eq!(checked_pow10(0).unwrap(), 1);
+        assert_eq!(checked_pow10(19).unwrap(), 10_000_000_000_000_000_000);
+        assert!(checked_pow10(20).is_err());
+    }
+
+    #[test]
+    fn add_secs_at_bounds() {
+        assert_eq!(add_secs(0, 30 * 24 * 60 * 60).unwrap(), 2_592_000);
+        assert_eq!(add_secs(i64::MAX - 1, 1).unwrap(), i64::MAX);
+        assert!(add_secs(i64::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn elapsed_secs_rejects_a_clock_behind_the_start() {
+        assert_eq!(elapsed_secs(100, 100).unwrap(), 0);
+        assert_eq!(elapsed_secs(100, 160).unwrap(), 60);
+        assert_eq!(
+            elapsed_secs(100, 99).unwrap_err(),
+            error!(SleekError::ClockError)
+        );
+    }
+}
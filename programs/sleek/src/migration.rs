@@ -0,0 +1,247 @@
+//! Forward migrations of the `SleekState` account layout
+//!
+//! Only `SleekState` is migrated. `Subscription`, `Payment` and `UserProfile`
+//! gained fields in the middle of their layouts without a version byte, so
+//! accounts written by an older program cannot be decoded; they have to be
+//! recreated rather than upgraded in place.
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use crate::{
+    SleekError, SleekState, DEFAULT_LOYALTY_MULTIPLIERS_BPS, DEFAULT_LOYALTY_THRESHOLDS,
+    DEFAULT_MIN_DURATION, DEFAULT_REFUND_WINDOW, DEFAULT_SAVINGS_LOCK, LOYALTY_TIER_COUNT,
+    DEFAULT_MAX_RENEWAL_FAILURES, MAX_SUBSCRIPTION_HORIZON, AUTO_SUBSCRIPTION_ID_START,
+    DEFAULT_RENEWAL_RETRY_SECS,
+};
+
+/// Layout version written by `initialize` and reached by `migrate_state`
+pub const STATE_VERSION: u8 = 21;
+
+/// `SleekState` as first deployed, before `bonk_mint` was inserted after `authority`
+#[derive(AnchorDeserialize)]
+struct GenesisState {
+    authority: Pubkey,
+    bump: u8,
+    total_subscriptions: u64,
+    total_payments: u64,
+    total_cashback_minted: u64,
+}
+
+/// Account size of the first deployed layout; every later layout is larger
+const GENESIS_STATE_LEN: usize = 8 + 32 + 1 + 8 + 8 + 8;
+
+/// Decode `SleekState` account data that may predate the current layout;
+/// trailing fields an older layout lacks read as zero
+///
+/// The first deployed layout has no `bonk_mint`, so decoding it needs the
+/// mint to fill it in.
+pub fn decode_state(data: &[u8], bonk_mint: Option<Pubkey>) -> Result<SleekState> {
+    if data.len() == GENESIS_STATE_LEN {
+        require!(
+            data[..8] == SleekState::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        let genesis = GenesisState::deserialize(&mut &data[8..])?;
+        let mut state = decode_state(&SleekState::DISCRIMINATOR, None)?;
+        state.authority = genesis.authority;
+        state.bonk_mint = bonk_mint.ok_or(SleekError::InvalidMint)?;
+        state.bump = genesis.bump;
+        state.total_subscriptions = genesis.total_subscriptions;
+        state.total_payments = genesis.total_payments;
+        state.total_cashback_minted = genesis.total_cashback_minted;
+        return Ok(state);
+    }
+
+    let mut padded = data.to_vec();
+    if padded.len() < 8 + SleekState::INIT_SPACE {
+        padded.resize(8 + SleekState::INIT_SPACE, 0);
+    }
+    SleekState::try_deserialize(&mut padded.as_slice())
+}
+
+/// Step `state` forward to `STATE_VERSION`, defaulting fields added since its version
+pub fn upgrade_state(state: &mut SleekState) -> Result<()> {
+    require!(state.version < STATE_VERSION, SleekError::StateUpToDate);
+
+    // Unversioned layouts may lack later settings for which zero is unusable
+    if state.version < 1 {
+        if state.loyalty_multipliers_bps == [0; LOYALTY_TIER_COUNT] {
+            state.loyalty_thresholds = DEFAULT_LOYALTY_THRESHOLDS;
+            state.loyalty_multipliers_bps = DEFAULT_LOYALTY_MULTIPLIERS_BPS;
+        }
+        if state.max_duration_secs == 0 {
+            state.min_duration_secs = DEFAULT_MIN_DURATION;
+            state.max_duration_secs = MAX_SUBSCRIPTION_HORIZON;
+        }
+    }
+
+    // v2 added `payment_decimals` and `cashback_decimals`; zero leaves cashback unscaled
+    // v3 added `min_redemption_amount`; zero disables the minimum
+    // v4 added `allowlist_enabled`; false leaves payments open to anyone
+    // v5 added `total_active_subscriptions`, which starts from zero and is not backfilled
+    // v6 added `keeper_reward` and `keeper_reward_budget`; zero pays keepers nothing
+    // v7 added `annual_discount_bps`; zero prices a year at twelve full cycles
+
+    // v8 added `refund_window_secs`; zero would silently stop all refunds
+    if state.version < 8 {
+        state.refund_window_secs = DEFAULT_REFUND_WINDOW;
+    }
+
+    // v9 added `trial_secs`; zero leaves free trials disabled
+    // v10 added `bonk_per_lamport`; zero leaves paying with cashback disabled
+    // v11 added `max_total_cashback` and `revert_on_cashback_budget`; zero leaves minting unbounded
+    // v12 added `admins`; empty leaves the authority as the only admin
+
+    // v13 added `savings_lock_secs`; zero would leave savings unlocked
+    if state.version < 13 {
+        state.savings_lock_secs = DEFAULT_SAVINGS_LOCK;
+    }
+
+    // v14 added `max_payments_per_window` and `payment_window_secs`; zero disables the limit
+    // v15 added `min_payment_amount`; zero disables the minimum
+
+    // v16 added `max_renewal_failures`; zero would keep failing subscriptions active forever
+    if state.version < 16 {
+        state.max_renewal_failures = DEFAULT_MAX_RENEWAL_FAILURES;
+    }
+
+    // v17 added `price_feed_id`, `price_tolerance_bps` and `max_price_age_secs`; a zero
+    // feed id leaves SOL prices unchecked
+    // v18 added `winback_window_secs` and `winback_bonus_bps`; a zero bonus disables winbacks
+    // v19 added `tier_features`; tiers grant no features until configured
+
+    // v20 added `next_subscription_id`; auto-assigned ids start above client-picked ones
+    if state.version < 20 {
+        state.next_subscription_id = AUTO_SUBSCRIPTION_ID_START;
+    }
+
+    // v21 added `renewal_retry_secs`; zero would let failed charges be retried at once
+    if state.version < 21 {
+        state.renewal_retry_secs = DEFAULT_RENEWAL_RETRY_SECS;
+    }
+
+    state.version = STATE_VERSION;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bytes each version appended to the layout, starting with v1's `version`
+    const ADDED_BYTES: [usize; STATE_VERSION as usize] = [1, 2, 8, 1, 8, 16, 2, 8, 8, 8, 9, 4, 8, 12, 8, 1, 42, 10, 12, 8, 8];
+
+    /// Serialized state cut back to the layout of `version`
+    fn legacy_data(state: &SleekState, version: u8) -> Vec<u8> {
+        let missing: usize = ADDED_BYTES[version as usize..].iter().sum();
+        let mut data = Vec::new();
+        state.try_serialize(&mut data).unwrap();
+        data.truncate(data.len() - missing);
+        data
+    }
+
+    fn zeroed_state() -> SleekState {
+        decode_state(&SleekState::DISCRIMINATOR, None).unwrap()
+    }
+
+    /// Account data as the first deployed program wrote it
+    fn genesis_data(authority: Pubkey, bump: u8, totals: [u64; 3]) -> Vec<u8> {
+        let mut data = SleekState::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(authority.as_ref());
+        data.push(bump);
+        for total in totals {
+            data.extend_from_slice(&total.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn migrates_the_genesis_layout() {
+        let authority = Pubkey::new_unique();
+        let bonk_mint = Pubkey::new_unique();
+        let data = genesis_data(authority, 254, [3, 7, 1_500]);
+        assert_eq!(data.len(), GENESIS_STATE_LEN);
+
+        let mut migrated = decode_state(&data, Some(bonk_mint)).unwrap();
+        assert_eq!(migrated.version, 0);
+        upgrade_state(&mut migrated).unwrap();
+
+        assert_eq!(migrated.version, STATE_VERSION);
+        assert_eq!(migrated.authority, authority);
+        assert_eq!(migrated.bonk_mint, bonk_mint);
+        assert_eq!(migrated.bump, 254);
+        assert_eq!(migrated.total_subscriptions, 3);
+        assert_eq!(migrated.total_payments, 7);
+        assert_eq!(migrated.total_cashback_minted, 1_500);
+        assert_eq!(migrated.loyalty_multipliers_bps, DEFAULT_LOYALTY_MULTIPLIERS_BPS);
+        assert_eq!(migrated.max_duration_secs, MAX_SUBSCRIPTION_HORIZON);
+    }
+
+    #[test]
+    fn requires_the_bonk_mint_for_the_genesis_layout() {
+        let data = genesis_data(Pubkey::new_unique(), 254, [0; 3]);
+        assert!(decode_state(&data, None).is_err());
+    }
+
+    #[test]
+    fn migrates_an_unversioned_layout() {
+        let mut state = zeroed_state();
+        state.authority = Pubkey::new_unique();
+        state.cashback_bps = 1_000;
+        state.loyalty_thresholds = [0, 5, 20];
+        state.loyalty_multipliers_bps = [10_000, 12_500, 15_000];
+        state.min_duration_secs = 60;
+        state.max_duration_secs = 3_600;
+
+        let mut migrated = decode_state(&legacy_data(&state, 0), None).unwrap();
+        assert_eq!(migrated.version, 0);
+        upgrade_state(&mut migrated).unwrap();
+
+        assert_eq!(migrated.version, STATE_VERSION);
+        assert_eq!(migrated.authority, state.authority);
+        assert_eq!(migrated.cashback_bps, 1_000);
+        assert_eq!(migrated.min_duration_secs, 60);
+        assert_eq!(migrated.max_duration_secs, 3_600);
+    }
+
+    #[test]
+    fn defaults_settings_missing_from_old_layouts() {
+        let mut migrated = zeroed_state();
+        upgrade_state(&mut migrated).unwrap();
+
+        assert_eq!(migrated.loyalty_thresholds, DEFAULT_LOYALTY_THRESHOLDS);
+        assert_eq!(migrated.loyalty_multipliers_bps, DEFAULT_LOYALTY_MULTIPLIERS_BPS);
+        assert_eq!(migrated.min_duration_secs, DEFAULT_MIN_DURATION);
+        assert_eq!(migrated.max_duration_secs, MAX_SUBSCRIPTION_HORIZON);
+        assert_eq!(migrated.next_subscription_id, AUTO_SUBSCRIPTION_ID_START);
+        assert_eq!(migrated.renewal_retry_secs, DEFAULT_RENEWAL_RETRY_SECS);
+    }
+
+    #[test]
+    fn migrates_a_v1_layout() {
+        let mut state = zeroed_state();
+        state.version = 1;
+        state.max_duration_secs = 3_600;
+
+        let mut migrated = decode_state(&legacy_data(&state, 1), None).unwrap();
+        assert_eq!(migrated.version, 1);
+        upgrade_state(&mut migrated).unwrap();
+
+        assert_eq!(migrated.version, STATE_VERSION);
+        assert_eq!(migrated.max_duration_secs, 3_600);
+        assert_eq!(migrated.payment_decimals, 0);
+        assert_eq!(migrated.cashback_decimals, 0);
+        assert_eq!(migrated.min_redemption_amount, 0);
+        assert!(!migrated.allowlist_enabled);
+        assert_eq!(migrated.refund_window_secs, DEFAULT_REFUND_WINDOW);
+        assert_eq!(migrated.max_renewal_failures, DEFAULT_MAX_RENEWAL_FAILURES);
+    }
+
+    #[test]
+    fn rejects_an_up_to_date_state() {
+        let mut state = zeroed_state();
+        state.version = STATE_VERSION;
+        assert!(upgrade_state(&mut state).is_err());
+    }
+}
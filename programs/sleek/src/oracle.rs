@@ -0,0 +1,278 @@
+//! SOL/USD price checks against a Pyth pull-oracle price feed
+//!
+//! Feeds are `PriceUpdateV2` accounts owned by the Pyth receiver program,
+//! decoded here directly instead of through the Pyth SDK. While
+//! `price_feed_id` is set, a SOL payment's `amount` is read as micro-USD and
+//! its `sol_amount` must come within `price_tolerance_bps` of what that buys
+//! at the feed's price.
+
+use anchor_lang::prelude::*;
+
+use crate::math::{checked_mul, checked_mul_div, checked_pow10};
+use crate::{SleekError, SleekState, BPS_DENOMINATOR};
+
+/// Pyth Solana receiver program `rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ`,
+/// owner of every `PriceUpdateV2` account
+pub const PYTH_RECEIVER_ID: Pubkey = Pubkey::new_from_array([
+    12, 183, 250, 187, 82, 247, 166, 72, 187, 91, 49, 125, 154, 1, 139, 144, 87, 203, 2, 71, 116,
+    250, 254, 1, 230, 196, 223, 152, 204, 56, 88, 129,
+]);
+
+/// Anchor discriminator of Pyth's `PriceUpdateV2` account
+const PRICE_UPDATE_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+/// Decimals `amount` is read in while the price check is on
+pub const USD_DECIMALS: i32 = 6;
+
+/// Decimals of a lamport
+const SOL_DECIMALS: i32 = 9;
+
+/// How many Wormhole guardian signatures backed a price update
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+/// Price of one feed at `publish_time`, worth `price * 10^exponent` USD
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct PriceFeedMessage {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+}
+
+/// Pyth's `PriceUpdateV2` account, less its discriminator
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct PriceUpdateV2 {
+    pub write_authority: Pubkey,
+    pub verification_level: VerificationLevel,
+    pub price_message: PriceFeedMessage,
+    pub posted_slot: u64,
+}
+
+/// The fully verified price in `info`, rejecting any feed but the configured one
+pub fn read_price(info: &AccountInfo, sleek_state: &SleekState) -> Result<PriceFeedMessage> {
+    require!(
+        info.owner == &PYTH_RECEIVER_ID,
+        SleekError::InvalidPriceFeed
+    );
+
+    let data = info.try_borrow_data()?;
+    require!(
+        data.len() >= 8 && data[..8] == PRICE_UPDATE_DISCRIMINATOR,
+        SleekError::InvalidPriceFeed
+    );
+    let update = PriceUpdateV2::deserialize(&mut &data[8..])
+        .map_err(|_| error!(SleekError::InvalidPriceFeed))?;
+
+    require!(
+        matches!(update.verification_level, VerificationLevel::Full),
+        SleekError::InvalidPriceFeed
+    );
+    require!(
+        update.price_message.feed_id == sleek_state.price_feed_id,
+        SleekError::InvalidPriceFeed
+    );
+    Ok(update.price_message)
+}
+
+/// Lamports `amount` micro-USD buys at `price`
+pub fn usd_to_lamports(amount: u64, price: &PriceFeedMessage) -> Result<u64> {
+    require!(price.price > 0, SleekError::InvalidPriceFeed);
+    let price_units = price.price as u64;
+
+    // lamports = amount * 10^(SOL_DECIMALS - USD_DECIMALS) / (price * 10^exponent)
+    let shift = SOL_DECIMALS - USD_DECIMALS - price.exponent;
+    let scale = checked_pow10(
+        u8::try_from(shift.unsigned_abs()).map_err(|_| error!(SleekError::ArithmeticOverflow))?,
+    )?;
+    if shift >= 0 {
+        checked_mul_div(amount, scale, price_units)
+    } else {
+        checked_mul_div(amount, 1, checked_mul(price_units, scale)?)
+    }
+}
+
+/// Check that `sol_amount` lamports is what `amount` micro-USD is worth at the
+/// feed's price, within the configured tolerance; a no-op without a feed id
+pub fn check_sol_amount(
+    feed: Option<&AccountInfo>,
+    amount: u64,
+    sol_amount: u64,
+    sleek_state: &SleekState,
+    now: i64,
+) -> Result<()> {
+    if sleek_state.price_feed_id == [0; 32] {
+        return Ok(());
+    }
+
+    let price = read_price(feed.ok_or(SleekError::InvalidPriceFeed)?, sleek_state)?;
+    require!(
+        now.saturating_sub(price.publish_time) <= sleek_state.max_price_age_secs,
+        SleekError::StalePrice
+    );
+
+    let expected = usd_to_lamports(amount, &price)?;
+    let tolerance = checked_mul_div(
+        expected,
+        sleek_state.price_tolerance_bps as u64,
+        BPS_DENOMINATOR,
+    )?;
+    require!(
+        sol_amount.abs_diff(expected) <= tolerance,
+        SleekError::PriceOutOfRange
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::decode_state;
+    use anchor_lang::Discriminator;
+
+    const FEED_ID: [u8; 32] = [7; 32];
+    const NOW: i64 = 1_700_000_000;
+
+    /// State checking `FEED_ID` within 1% and 60 seconds
+    fn oracle_state() -> SleekState {
+        let mut state = decode_state(&SleekState::DISCRIMINATOR, None).unwrap();
+        state.price_feed_id = FEED_ID;
+        state.price_tolerance_bps = 100;
+        state.max_price_age_secs = 60;
+        state
+    }
+
+    /// Serialized `PriceUpdateV2` quoting SOL at `price * 10^-8` USD
+    fn feed_data(
+        feed_id: [u8; 32],
+        price: i64,
+        publish_time: i64,
+        level: VerificationLevel,
+    ) -> Vec<u8> {
+        let mut data = PRICE_UPDATE_DISCRIMINATOR.to_vec();
+        PriceUpdateV2 {
+            write_authority: Pubkey::new_unique(),
+            verification_level: level,
+            price_message: PriceFeedMessage {
+                feed_id,
+                price,
+                conf: 0,
+                exponent: -8,
+                publish_time,
+                prev_publish_time: publish_time - 1,
+                ema_price: price,
+                ema_conf: 0,
+            },
+            posted_slot: 1,
+        }
+        .serialize(&mut data)
+        .unwrap();
+        data
+    }
+
+    /// Run `check_sol_amount` for $10 against a feed owned by `owner`
+    fn check(owner: &Pubkey, mut data: Vec<u8>, sol_amount: u64) -> Result<()> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            owner,
+            false,
+            0,
+        );
+        check_sol_amount(Some(&info), 10_000_000, sol_amount, &oracle_state(), NOW)
+    }
+
+    // $10 at $200 per SOL is 0.05 SOL
+    const PRICE: i64 = 20_000_000_000;
+    const EXPECTED: u64 = 50_000_000;
+
+    #[test]
+    fn pins_the_pyth_receiver_program() {
+        assert_eq!(
+            PYTH_RECEIVER_ID.to_string(),
+            "rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ"
+        );
+    }
+
+    #[test]
+    fn converts_usd_to_lamports() {
+        let data = feed_data(FEED_ID, PRICE, NOW, VerificationLevel::Full);
+        let price = PriceUpdateV2::deserialize(&mut &data[8..])
+            .unwrap()
+            .price_message;
+        assert_eq!(usd_to_lamports(10_000_000, &price).unwrap(), EXPECTED);
+    }
+
+    #[test]
+    fn accepts_prices_within_tolerance() {
+        let fresh = || feed_data(FEED_ID, PRICE, NOW - 60, VerificationLevel::Full);
+        check(&PYTH_RECEIVER_ID, fresh(), EXPECTED).unwrap();
+        check(&PYTH_RECEIVER_ID, fresh(), EXPECTED + EXPECTED / 100).unwrap();
+        check(&PYTH_RECEIVER_ID, fresh(), EXPECTED - EXPECTED / 100).unwrap();
+    }
+
+    #[test]
+    fn rejects_prices_out_of_tolerance() {
+        let fresh = || feed_data(FEED_ID, PRICE, NOW, VerificationLevel::Full);
+        for sol_amount in [EXPECTED + EXPECTED / 100 + 1, EXPECTED - EXPECTED / 100 - 1] {
+            assert_eq!(
+                check(&PYTH_RECEIVER_ID, fresh(), sol_amount).unwrap_err(),
+                error!(SleekError::PriceOutOfRange)
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_a_stale_price() {
+        let stale = feed_data(FEED_ID, PRICE, NOW - 61, VerificationLevel::Full);
+        assert_eq!(
+            check(&PYTH_RECEIVER_ID, stale, EXPECTED).unwrap_err(),
+            error!(SleekError::StalePrice)
+        );
+    }
+
+    #[test]
+    fn rejects_untrusted_feeds() {
+        let full = feed_data(FEED_ID, PRICE, NOW, VerificationLevel::Full);
+        let other_feed = feed_data([8; 32], PRICE, NOW, VerificationLevel::Full);
+        let partial = feed_data(
+            FEED_ID,
+            PRICE,
+            NOW,
+            VerificationLevel::Partial { num_signatures: 5 },
+        );
+        for (owner, data) in [
+            (Pubkey::new_unique(), full),
+            (PYTH_RECEIVER_ID, other_feed),
+            (PYTH_RECEIVER_ID, partial),
+        ] {
+            assert_eq!(
+                check(&owner, data, EXPECTED).unwrap_err(),
+                error!(SleekError::InvalidPriceFeed)
+            );
+        }
+        assert_eq!(
+            check_sol_amount(None, 10_000_000, EXPECTED, &oracle_state(), NOW).unwrap_err(),
+            error!(SleekError::InvalidPriceFeed)
+        );
+    }
+
+    #[test]
+    fn skips_the_check_without_a_feed_id() {
+        let mut state = oracle_state();
+        state.price_feed_id = [0; 32];
+        check_sol_amount(None, 10_000_000, 1, &state, NOW).unwrap();
+    }
+}
@@ -0,0 +1,552 @@
+//! Subscription payment flow shared by the SPL token and native SOL instructions
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+use crate::cashback::{apply_cashback_budget, apply_cashback_cap, mint_cashback};
+use crate::math::{add_secs, checked_add, checked_mul, checked_mul_div, checked_sub};
+use crate::status::transition;
+use crate::{
+    apply_discount, calculate_cashback, calculate_payment_cashback,
+    BillingPeriod, CashbackApplied, CashbackReason, Coupon, Payment, PaymentProcessed,
+    PaymentSplit, PaymentStatus, Plan, ReferralRewarded, SleekError, SleekState, Subscription,
+    SubscriptionCreated, SubscriptionIndex, SubscriptionStatus, Tier, UserProfile,
+    ANNUAL_DURATION, BPS_DENOMINATOR, MAX_METADATA_URI_LEN, MAX_TAG_LEN, MONTHS_PER_YEAR,
+};
+
+/// Instruction arguments of a subscription payment
+pub struct PaymentRequest {
+    pub subscription_id: u64,
+    pub amount: u64,
+    pub sol_amount: u64,
+    pub tier: Tier,
+    pub referrer: Option<Pubkey>,
+    pub duration_secs: Option<i64>,
+    pub max_sol_amount: u64,
+    pub metadata_uri: String,
+    pub billing_period: BillingPeriod,
+    /// Most BONK the user will burn for a discount; zero pays in full
+    pub cashback_to_apply: u64,
+    /// Correlation id recorded on a `FulfillmentIntent` for off-chain provisioning
+    pub service_ref: Option<[u8; 32]>,
+    /// Accounting tag stored on the subscription
+    pub tag: String,
+}
+
+/// What a validated request costs and buys
+pub struct PaymentTerms {
+    pub amount: u64,
+    pub sol_amount: u64,
+    pub list_amount: u64,
+    pub list_sol_amount: u64,
+    pub fee_amount: u64,
+    pub treasury_amount: u64,
+    pub cashback_burned: u64,
+    pub cashback_discount: u64,
+    pub duration_secs: i64,
+    pub plan_cashback_bps: Option<u16>,
+    /// Tier recorded on the subscription; a plan's own tier overrides the client's
+    pub tier: Tier,
+    pub is_new_subscription: bool,
+    pub still_counted: bool,
+}
+
+/// BONK owed once a recorded payment's funds have moved
+pub struct PaymentRewards {
+    pub cashback_amount: u64,
+    /// Share of `cashback_amount` minted into the user's savings vault
+    pub savings_amount: u64,
+    pub referral: Option<(Pubkey, u64)>,
+}
+
+/// Bumps of the PDAs a payment initializes
+pub struct PaymentBumps {
+    pub payment: u8,
+    pub subscription: u8,
+    pub user_profile: u8,
+    pub subscription_index: u8,
+}
+
+/// Accounts a payment touches besides the ones funds move through
+pub struct PaymentAccounts<'a, 'info> {
+    pub payment: &'a mut Account<'info, Payment>,
+    pub subscription: &'a mut Account<'info, Subscription>,
+    pub user_profile: &'a mut Account<'info, UserProfile>,
+    pub subscription_index: &'a mut Account<'info, SubscriptionIndex>,
+    pub sleek_state: &'a mut Account<'info, SleekState>,
+    pub user: &'a Signer<'info>,
+    pub user_bonk_account: &'a Account<'info, TokenAccount>,
+    pub bonk_mint: &'a Account<'info, Mint>,
+    pub referrer_bonk_account: Option<&'a Account<'info, TokenAccount>>,
+    pub savings_bonk_account: Option<&'a Account<'info, TokenAccount>>,
+    pub coupon: Option<&'a mut Account<'info, Coupon>>,
+    pub plan: Option<&'a mut Account<'info, Plan>>,
+    pub authority: &'a Signer<'info>,
+    pub token_program: &'a Program<'info, Token>,
+    pub payment_mint: Pubkey,
+    pub bumps: PaymentBumps,
+    /// Timestamp the payment is recorded at, read once through `clock::now`
+    pub now: i64,
+}
+
+impl<'a, 'info> PaymentAccounts<'a, 'info> {
+    /// Validate a request and work out the price, duration and fee split
+    pub fn quote(&mut self, request: &PaymentRequest) -> Result<PaymentTerms> {
+        let sleek_state = &self.sleek_state;
+
+        require!(!sleek_state.paused, SleekError::ProgramPaused);
+        require!(
+            !sleek_state.allowlist_enabled || self.user_profile.allowlisted,
+            SleekError::NotAllowlisted
+        );
+
+        // A plan fixes the price, duration and cashback rate, overriding client values
+        let (amount, sol_amount, plan_terms) = match self.plan.as_deref() {
+            Some(plan) => {
+                require!(plan.active, SleekError::PlanInactive);
+                require!(
+                    plan.payment_mint == self.payment_mint,
+                    SleekError::UnsupportedPaymentMint
+                );
+                (plan.price, plan.price, Some((plan.duration_secs, plan.cashback_bps)))
+            }
+            None => (request.amount, request.sol_amount, None),
+        };
+
+        // Annual billing charges twelve monthly cycles less the annual discount
+        let (amount, sol_amount) = match request.billing_period {
+            BillingPeriod::Monthly => (amount, sol_amount),
+            BillingPeriod::Annual => {
+                require!(
+                    plan_terms.is_none() && request.duration_secs.is_none(),
+                    SleekError::InvalidBillingPeriod
+                );
+                let annual_price = |monthly: u64| {
+                    apply_discount(
+                        checked_mul(monthly, MONTHS_PER_YEAR)?,
+                        sleek_state.annual_discount_bps,
+                    )
+                };
+                (annual_price(amount)?, annual_price(sol_amount)?)
+            }
+        };
+
+        require!(amount > 0, SleekError::InvalidAmount);
+        require!(sol_amount > 0, SleekError::InvalidAmount);
+        require!(sol_amount <= request.max_sol_amount, SleekError::PriceSlippageExceeded);
+        require!(
+            request.referrer != Some(self.user.key()),
+            SleekError::SelfReferral
+        );
+        require!(
+            request.metadata_uri.len() <= MAX_METADATA_URI_LEN,
+            SleekError::UriTooLong
+        );
+        require!(request.tag.len() <= MAX_TAG_LEN, SleekError::TagTooLong);
+
+        // An id may be bought again only once its previous subscription has ended
+        let existing = &self.subscription;
+        let is_new_subscription = existing.user == Pubkey::default();
+        let still_counted = existing.user != Pubkey::default()
+            && matches!(existing.status, SubscriptionStatus::Active | SubscriptionStatus::Paused);
+        if still_counted {
+            require!(
+                existing.effective_status(self.now, sleek_state.grace_period_secs)
+                    == SubscriptionStatus::Expired,
+                SleekError::SubscriptionAlreadyExists
+            );
+            // The lapsed period still counts toward its plan, which is not passed
+            // here; it has to be cranked expired before moving to another plan
+            require!(
+                existing.plan_id.is_none()
+                    || existing.plan_id == self.plan.as_deref().map(|plan| plan.plan_id),
+                SleekError::PlanMismatch
+            );
+        } else {
+            self.user_profile.require_subscription_slot(sleek_state)?;
+        }
+
+        // A custom duration overrides the tier's billing cycle
+        let duration_secs = match (plan_terms, request.duration_secs) {
+            _ if request.billing_period == BillingPeriod::Annual => ANNUAL_DURATION,
+            (Some((plan_duration_secs, _)), _) => plan_duration_secs,
+            (None, Some(duration_secs)) => {
+                require!(
+                    (sleek_state.min_duration_secs..=sleek_state.max_duration_secs)
+                        .contains(&duration_secs),
+                    SleekError::InvalidDuration
+                );
+                duration_secs
+            }
+            (None, None) => sleek_state.tier_configs[request.tier.index()].duration_secs,
+        };
+
+        // Apply an optional coupon to both the price and the amount transferred
+        let list_amount = amount;
+        let list_sol_amount = sol_amount;
+        let (amount, sol_amount) = match self.coupon.as_mut() {
+            Some(coupon) => {
+                require!(
+                    self.now < coupon.expiry
+                        && coupon.times_redeemed < coupon.max_redemptions,
+                    SleekError::CouponInvalid
+                );
+                coupon.times_redeemed = checked_add(coupon.times_redeemed, 1)?;
+                (
+                    apply_discount(amount, coupon.discount_bps)?,
+                    apply_discount(sol_amount, coupon.discount_bps)?,
+                )
+            }
+            None => (amount, sol_amount),
+        };
+
+        // Burned cashback comes off what is owed, never past the full price. Spending
+        // it is a redemption, so it is held back for frozen users and paused redemptions
+        let (cashback_discount, cashback_burned) = if request.cashback_to_apply > 0 {
+            require!(!self.user_profile.frozen, SleekError::AccountFrozen);
+            require!(!self.sleek_state.redemptions_paused, SleekError::RedemptionsPaused);
+            let bonk_per_lamport = self.sleek_state.bonk_per_lamport;
+            require!(bonk_per_lamport > 0, SleekError::CashbackPaymentsDisabled);
+            let discount = (request.cashback_to_apply / bonk_per_lamport).min(sol_amount);
+            let burned = checked_mul(discount, bonk_per_lamport)?;
+            require!(
+                burned <= self.user_bonk_account.amount,
+                SleekError::InsufficientBalance
+            );
+            (discount, burned)
+        } else {
+            (0, 0)
+        };
+        // Cashback accrues only on the share of the price still paid for
+        let paid_sol_amount = checked_sub(sol_amount, cashback_discount)?;
+        let amount = match cashback_discount {
+            0 => amount,
+            _ => checked_mul_div(amount, paid_sol_amount, sol_amount)?,
+        };
+        let sol_amount = paid_sol_amount;
+        require!(
+            sol_amount >= self.sleek_state.min_payment_amount,
+            SleekError::PaymentTooSmall
+        );
+
+        // Split the payment between the partner and the treasury
+        let (fee_amount, treasury_amount) = self.sleek_state.split_fee(sol_amount)?;
+
+        Ok(PaymentTerms {
+            amount,
+            sol_amount,
+            list_amount,
+            list_sol_amount,
+            fee_amount,
+            treasury_amount,
+            cashback_burned,
+            cashback_discount,
+            duration_secs,
+            plan_cashback_bps: plan_terms.map(|(_, cashback_bps)| cashback_bps),
+            tier: self.plan.as_deref().map_or(request.tier, |plan| plan.tier),
+            is_new_subscription,
+            still_counted,
+        })
+    }
+
+    /// Record a quoted payment and activate its subscription, returning the
+    /// BONK to mint once funds have moved. Nothing irreversible happens here,
+    /// so callers transfer funds and mint rewards only after this succeeds
+    pub fn settle(
+        &mut self,
+        request: &PaymentRequest,
+        terms: &PaymentTerms,
+    ) -> Result<PaymentRewards> {
+        let PaymentRequest { subscription_id, referrer, .. } = *request;
+        let PaymentTerms { amount, sol_amount, tier, .. } = *terms;
+        let sleek_state = &mut *self.sleek_state;
+
+        // Set payment details
+        let payment = &mut *self.payment;
+        payment.user = self.user.key();
+        payment.subscription_id = subscription_id;
+        payment.amount = amount;
+        payment.sol_amount = sol_amount;
+        payment.payment_mint = self.payment_mint;
+        payment.status = PaymentStatus::Completed;
+        payment.fee_amount = terms.fee_amount;
+        payment.timestamp = self.now;
+        payment.bump = self.bumps.payment;
+
+        sleek_state.total_collected =
+            checked_add(sleek_state.total_collected, terms.treasury_amount)?;
+
+        if terms.fee_amount > 0 {
+            emit!(PaymentSplit {
+                user: self.user.key(),
+                fee_recipient: sleek_state.fee_recipient,
+                fee_amount: terms.fee_amount,
+                treasury_amount: terms.treasury_amount,
+            });
+        }
+
+        // Calculate cashback at the tier's rate, limited by the user's cap
+        let user_profile = &mut *self.user_profile;
+        user_profile.user = self.user.key();
+        user_profile.bump = self.bumps.user_profile;
+
+        let now = self.now;
+        user_profile.record_payment(now, sleek_state)?;
+        let promo_applied = terms.plan_cashback_bps.is_none() && sleek_state.promo_active(now);
+        let cashback_bps = sleek_state.payment_cashback_bps(
+            terms.plan_cashback_bps,
+            tier,
+            user_profile.total_payments,
+            now,
+        );
+        let cashback_bps =
+            user_profile.apply_winback(cashback_bps, subscription_id, now, sleek_state)?;
+        let cashback_amount = apply_cashback_cap(
+            calculate_payment_cashback(amount, cashback_bps, sleek_state)?,
+            user_profile.total_cashback_earned,
+            sleek_state,
+        )?;
+        let cashback_amount =
+            apply_cashback_budget(cashback_amount, sleek_state.total_cashback_minted, sleek_state)?;
+
+        payment.cashback_minted = cashback_amount;
+
+        // Lock the user's chosen share away, restarting the savings lock
+        let savings_amount = checked_mul_div(
+            cashback_amount,
+            user_profile.savings_split_bps as u64,
+            BPS_DENOMINATOR,
+        )?;
+        if savings_amount > 0 {
+            require!(
+                self.savings_bonk_account.is_some(),
+                SleekError::SavingsAccountMissing
+            );
+            user_profile.savings_unlock_at = add_secs(now, sleek_state.savings_lock_secs)?;
+        }
+
+        // Reward the referrer on the user's first payment
+        let mut referral = None;
+        if let Some(referrer) = referrer {
+            if user_profile.total_payments == 0 {
+                let referrer_bonk_account = self
+                    .referrer_bonk_account
+                    .ok_or(SleekError::InvalidReferrer)?;
+                require!(
+                    referrer_bonk_account.owner == referrer,
+                    SleekError::InvalidReferrer
+                );
+
+                let referral_amount = apply_cashback_budget(
+                    sleek_state.to_cashback_units(calculate_cashback(
+                        amount,
+                        sleek_state.referral_bonus_bps,
+                    )?)?,
+                    checked_add(sleek_state.total_cashback_minted, cashback_amount)?,
+                    sleek_state,
+                )?;
+                referral = Some((referrer, referral_amount));
+            }
+        }
+
+        // Update global stats; `mint_rewards` counts the cashback once minted
+        sleek_state.total_payments = checked_add(sleek_state.total_payments, 1)?;
+
+        // Create subscription NFT
+        let subscription = &mut *self.subscription;
+        let previous_plan_id = subscription.plan_id;
+        subscription.user = self.user.key();
+        subscription.subscription_id = subscription_id;
+        subscription.amount = terms.list_amount;
+        subscription.sol_price = terms.list_sol_amount;
+        subscription.amount_paid = sol_amount;
+        subscription.treasury_paid = terms.treasury_amount;
+        subscription.auto_renew = false;
+        subscription.plan_id = self.plan.as_ref().map(|plan| plan.plan_id);
+        subscription.metadata_uri = request.metadata_uri.clone();
+        subscription.tag = request.tag.clone();
+        subscription.renewal_failures = 0;
+        subscription.billing_period = request.billing_period;
+        subscription.tier = tier;
+        // A re-purchase brings an ended subscription back; a lapsed one never left `Active`
+        if !terms.is_new_subscription && subscription.status != SubscriptionStatus::Active {
+            transition(subscription.status, SubscriptionStatus::Active)?;
+        }
+        subscription.status = SubscriptionStatus::Active;
+        subscription.cancellation_date = None;
+        subscription.cancellation_reason = None;
+        subscription.activation_date = now;
+        subscription.expiration_date = add_secs(now, terms.duration_secs)?;
+        subscription.period_secs = terms.duration_secs;
+        subscription.last_payment = payment.key();
+        subscription.period_cashback = cashback_amount;
+        subscription.bump = self.bumps.subscription;
+
+        sleek_state.total_subscriptions = checked_add(sleek_state.total_subscriptions, 1)?;
+
+        if let Some(plan) = self.plan.as_deref_mut() {
+            plan.lifetime_subscribers = checked_add(plan.lifetime_subscribers, 1)?;
+            // A still-counted re-purchase already counts only if it stays on this plan
+            if !terms.still_counted || previous_plan_id != Some(plan.plan_id) {
+                plan.active_subscribers = checked_add(plan.active_subscribers, 1)?;
+            }
+        }
+
+        // Update per-user stats alongside the global counters
+        if user_profile.first_seen == 0 {
+            user_profile.first_seen = subscription.activation_date;
+        }
+        user_profile.total_spent = checked_add(user_profile.total_spent, sol_amount)?;
+        user_profile.total_payments = checked_add(user_profile.total_payments, 1)?;
+        user_profile.total_cashback_earned =
+            checked_add(user_profile.total_cashback_earned, cashback_amount)?;
+        if !terms.still_counted {
+            user_profile.active_subscriptions = checked_add(user_profile.active_subscriptions, 1)?;
+            sleek_state.total_active_subscriptions =
+                checked_add(sleek_state.total_active_subscriptions, 1)?;
+        }
+
+        // Record new ids in the user's subscription index
+        if terms.is_new_subscription {
+            let subscription_index = &mut *self.subscription_index;
+            subscription_index.user = self.user.key();
+            subscription_index.page = user_profile.index_page();
+            subscription_index.bump = self.bumps.subscription_index;
+            subscription_index.subscription_ids.push(subscription_id);
+            user_profile.indexed_subscriptions = checked_add(user_profile.indexed_subscriptions, 1)?;
+        }
+
+        emit!(PaymentProcessed {
+            user: self.user.key(),
+            subscription_id,
+            amount,
+            cashback_amount,
+            tier,
+            timestamp: payment.timestamp,
+            payment: payment.key(),
+            sol_amount,
+            expiration_date: subscription.expiration_date,
+            cashback_bps,
+            promo_applied,
+        });
+
+        emit!(SubscriptionCreated {
+            user: self.user.key(),
+            subscription_id,
+            tier,
+            activation_date: subscription.activation_date,
+            expiration_date: subscription.expiration_date,
+            billing_period: request.billing_period,
+            tag: request.tag.clone(),
+        });
+
+        Ok(PaymentRewards {
+            cashback_amount,
+            savings_amount,
+            referral,
+        })
+    }
+
+    /// Burn the cashback a settled payment was discounted by
+    pub fn burn_applied_cashback(
+        &self,
+        request: &PaymentRequest,
+        terms: &PaymentTerms,
+    ) -> Result<()> {
+        if terms.cashback_burned == 0 {
+            return Ok(());
+        }
+
+        let burn_ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            token::Burn {
+                mint: self.bonk_mint.to_account_info(),
+                from: self.user_bonk_account.to_account_info(),
+                authority: self.user.to_account_info(),
+            },
+        );
+        token::burn(burn_ctx, terms.cashback_burned)?;
+
+        emit!(CashbackApplied {
+            user: self.user.key(),
+            subscription_id: request.subscription_id,
+            cashback_burned: terms.cashback_burned,
+            discount: terms.cashback_discount,
+        });
+
+        Ok(())
+    }
+
+    /// Mint the cashback and referral reward of a settled payment
+    pub fn mint_rewards(&mut self, rewards: &PaymentRewards) -> Result<()> {
+        // Both amounts were already limited by `settle`
+        let mint_ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            token::MintTo {
+                mint: self.bonk_mint.to_account_info(),
+                to: self.user_bonk_account.to_account_info(),
+                authority: self.authority.to_account_info(),
+            },
+        );
+        mint_cashback(
+            mint_ctx,
+            self.sleek_state,
+            None,
+            self.user.key(),
+            checked_sub(rewards.cashback_amount, rewards.savings_amount)?,
+            CashbackReason::Payment,
+        )?;
+
+        if rewards.savings_amount > 0 {
+            let savings_bonk_account = self
+                .savings_bonk_account
+                .ok_or(SleekError::SavingsAccountMissing)?;
+            let savings_ctx = CpiContext::new(
+                self.token_program.to_account_info(),
+                token::MintTo {
+                    mint: self.bonk_mint.to_account_info(),
+                    to: savings_bonk_account.to_account_info(),
+                    authority: self.authority.to_account_info(),
+                },
+            );
+            mint_cashback(
+                savings_ctx,
+                self.sleek_state,
+                None,
+                self.user.key(),
+                rewards.savings_amount,
+                CashbackReason::Payment,
+            )?;
+        }
+
+        if let Some((referrer, referral_amount)) = rewards.referral {
+            let referrer_bonk_account = self
+                .referrer_bonk_account
+                .ok_or(SleekError::InvalidReferrer)?;
+            let referral_ctx = CpiContext::new(
+                self.token_program.to_account_info(),
+                token::MintTo {
+                    mint: self.bonk_mint.to_account_info(),
+                    to: referrer_bonk_account.to_account_info(),
+                    authority: self.authority.to_account_info(),
+                },
+            );
+            mint_cashback(
+                referral_ctx,
+                self.sleek_state,
+                None,
+                referrer,
+                referral_amount,
+                CashbackReason::Referral,
+            )?;
+
+            emit!(ReferralRewarded {
+                referrer,
+                user: self.user.key(),
+                amount: referral_amount,
+            });
+        }
+
+        Ok(())
+    }
+}
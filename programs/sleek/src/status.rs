@@ -0,0 +1,64 @@
+//! Legal `SubscriptionStatus` transitions
+
+use anchor_lang::prelude::*;
+
+use crate::{SleekError, SubscriptionStatus};
+
+/// Check that a subscription may move from `from` to `to`
+///
+/// Active subscriptions may be cancelled, expire or be paused; cancelled ones
+/// may be reactivated, expired ones renewed and paused ones resumed. Everything
+/// else, including staying in the same status, fails with
+/// `SleekError::InvalidStatusTransition`.
+pub fn transition(from: SubscriptionStatus, to: SubscriptionStatus) -> Result<()> {
+    use SubscriptionStatus::*;
+
+    match (from, to) {
+        (Active, Cancelled)
+        | (Active, Expired)
+        | (Active, Paused)
+        | (Cancelled, Active)
+        | (Expired, Active)
+        | (Paused, Active) => Ok(()),
+        _ => err!(SleekError::InvalidStatusTransition),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use SubscriptionStatus::*;
+
+    const ALL: [SubscriptionStatus; 4] = [Active, Expired, Cancelled, Paused];
+
+    #[test]
+    fn legal_transitions() {
+        for (from, to) in [
+            (Active, Cancelled),
+            (Active, Expired),
+            (Active, Paused),
+            (Cancelled, Active),
+            (Expired, Active),
+            (Paused, Active),
+        ] {
+            assert!(transition(from, to).is_ok());
+        }
+    }
+
+    #[test]
+    fn illegal_transitions() {
+        for (from, to) in [
+            (Cancelled, Expired),
+            (Expired, Cancelled),
+            (Paused, Cancelled),
+            (Paused, Expired),
+            (Cancelled, Paused),
+            (Expired, Paused),
+        ] {
+            assert!(transition(from, to).is_err());
+        }
+        for status in ALL {
+            assert!(transition(status, status).is_err());
+        }
+    }
+}
@@ -0,0 +1,53 @@
+import { PublicKey } from '@solana/web3.js';
+import { createHash } from 'crypto';
+import * as fs from 'fs';
+import * as path from 'path';
+
+// Owner of the clock accounts; must match declare_id! in programs/sleek
+const PROGRAM_ID = 'Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS';
+
+// Rent-exempt balance of a 16-byte account
+const CLOCK_LAMPORTS = 1_002_240;
+
+// The clock at offset zero reads 2100-01-01, past any real validator time
+export const TEST_CLOCK_START = 4_102_444_800;
+
+// Largest offset, in seconds, a preloaded clock reads
+export const TEST_CLOCK_MAX_OFFSET = 600;
+
+// Address of the clock reading TEST_CLOCK_START + offset
+export const testClockAddress = (offset: number) =>
+  new PublicKey(createHash('sha256').update(`test-clock:${offset}`).digest());
+
+// Writes one TestClock account per second of offset for the local validator
+// to preload from tests/fixtures/clock
+function main() {
+  const dir = path.join(__dirname, '..', 'tests', 'fixtures', 'clock');
+  fs.mkdirSync(dir, { recursive: true });
+
+  const discriminator = createHash('sha256').update('account:TestClock').digest().subarray(0, 8);
+  for (let offset = 0; offset <= TEST_CLOCK_MAX_OFFSET; offset++) {
+    const data = Buffer.alloc(16);
+    discriminator.copy(data);
+    data.writeBigInt64LE(BigInt(TEST_CLOCK_START + offset), 8);
+
+    const fixture = {
+      pubkey: testClockAddress(offset).toBase58(),
+      account: {
+        lamports: CLOCK_LAMPORTS,
+        data: [data.toString('base64'), 'base64'],
+        owner: PROGRAM_ID,
+        executable: false,
+        rentEpoch: 0,
+        space: data.length,
+      },
+    };
+    fs.writeFileSync(path.join(dir, `${offset}.json`), JSON.stringify(fixture));
+  }
+
+  console.log(`Wrote ${TEST_CLOCK_MAX_OFFSET + 1} test clocks to ${dir}`);
+}
+
+if (require.main === module) {
+  main();
+}